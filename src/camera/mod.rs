@@ -0,0 +1,259 @@
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray},
+    world::World,
+};
+
+pub struct Camera {
+    pub hsize: u32,
+    pub vsize: u32,
+    pub field_of_view: f64,
+    transform: Matrix4,
+    inverse: Option<Matrix4>,
+    half_width: f64,
+    half_height: f64,
+    pixel_size: f64,
+}
+
+impl Camera {
+    pub fn new(hsize: u32, vsize: u32, field_of_view: f64) -> Self {
+        let half_view = (field_of_view / 2.).tan();
+        let aspect = hsize as f64 / vsize as f64;
+        let (half_width, half_height) = if aspect >= 1. {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Matrix4::identity(),
+            inverse: Some(Matrix4::identity()),
+            half_width,
+            half_height,
+            pixel_size: half_width * 2. / hsize as f64,
+        }
+    }
+
+    pub fn transform(&self) -> Matrix4 {
+        self.transform
+    }
+
+    /// Sets the view transformation, working out the inverse that takes
+    /// canvas points back into the world once up front.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = transform;
+        self.inverse = transform.inverse().ok();
+    }
+
+    fn inverse(&self) -> Result<Matrix4, StingrayError> {
+        self.inverse
+            .ok_or_else(|| StingrayError::Singular.context("camera transformation"))
+    }
+
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    /// The ray leaving the camera through the center of pixel (`x`, `y`).
+    pub fn ray_for_pixel(&self, x: u32, y: u32) -> Result<Ray, StingrayError> {
+        let inverse = self.inverse()?;
+        Ok(self.ray_through(&inverse, x as f64 + 0.5, y as f64 + 0.5))
+    }
+
+    pub fn render(&self, world: &World) -> Result<Canvas, StingrayError> {
+        self.render_samples(world, 1)
+    }
+
+    /// Renders `world` averaging `samples` rays per pixel. The sample
+    /// positions are stratified along x and spread along y by a base-2
+    /// radical inverse, so any count covers the pixel evenly; a single
+    /// sample goes through the pixel center.
+    pub fn render_samples(&self, world: &World, samples: u32) -> Result<Canvas, StingrayError> {
+        if samples == 0 {
+            return Err(StingrayError::InvalidArgument(
+                "at least one sample per pixel is required".to_string(),
+            ));
+        }
+        let inverse = self.inverse()?;
+        let offsets = sample_offsets(samples);
+        let mut canvas = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let mut color = Color::black();
+                for &(dx, dy) in &offsets {
+                    let ray = self.ray_through(&inverse, x as f64 + dx, y as f64 + dy);
+                    color = color + world.color_at(&ray)?;
+                }
+                canvas.set_pixel(x, y, color * (1. / samples as f64));
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Casts a ray through the canvas position (`px`, `py`), measured in
+    /// pixels from the top left corner.
+    fn ray_through(&self, inverse: &Matrix4, px: f64, py: f64) -> Ray {
+        // The canvas sits at z = -1 in camera space, with +x pointing left
+        let world_x = self.half_width - px * self.pixel_size;
+        let world_y = self.half_height - py * self.pixel_size;
+
+        let pixel = *inverse * Point::new(world_x, world_y, -1.);
+        let origin = *inverse * Point::new(0., 0., 0.);
+        Ray::new(origin, (pixel - origin).norm())
+    }
+}
+
+/// Sub-pixel offsets in [0, 1) for `samples` rays through one pixel.
+fn sample_offsets(samples: u32) -> Vec<(f64, f64)> {
+    let half_step = 0.5 / samples as f64;
+    (0..samples)
+        .map(|i| {
+            let x = i as f64 / samples as f64 + half_step;
+            let y = (radical_inverse(i) + half_step).fract();
+            (x, y)
+        })
+        .collect()
+}
+
+/// Mirrors the binary digits of `i` around the radix point.
+fn radical_inverse(i: u32) -> f64 {
+    i.reverse_bits() as f64 / (1u64 << 32) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+    use crate::{
+        geo::{matrix::Rotation, vector::Vector},
+        test::utils::default_world,
+        traits::SpecificRound,
+    };
+
+    #[test]
+    fn test_camera_new() {
+        let camera = Camera::new(160, 120, PI / 2.);
+
+        assert_eq!(camera.hsize, 160);
+        assert_eq!(camera.vsize, 120);
+        assert_eq!(camera.field_of_view, PI / 2.);
+        assert_eq!(camera.transform(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_pixel_size() {
+        let horizontal = Camera::new(200, 125, PI / 2.);
+        let vertical = Camera::new(125, 200, PI / 2.);
+
+        assert_eq!(horizontal.pixel_size().specific_round(5), 0.01);
+        assert_eq!(vertical.pixel_size().specific_round(5), 0.01);
+    }
+
+    #[test]
+    fn test_ray_through_center() {
+        let camera = Camera::new(201, 101, PI / 2.);
+        let ray = camera.ray_for_pixel(100, 50).unwrap();
+
+        assert_eq!(ray.origin, Point::new(0., 0., 0.));
+        assert_eq!(ray.direction.z.specific_round(5), -1.);
+        assert_eq!(ray.direction.x.specific_round(5), 0.);
+    }
+
+    #[test]
+    fn test_ray_through_corner() {
+        let camera = Camera::new(201, 101, PI / 2.);
+        let ray = camera.ray_for_pixel(0, 0).unwrap();
+
+        assert_eq!(ray.origin, Point::new(0., 0., 0.));
+        assert_eq!(ray.direction.x.specific_round(5), 0.66519);
+        assert_eq!(ray.direction.y.specific_round(5), 0.33259);
+        assert_eq!(ray.direction.z.specific_round(5), -0.66851);
+    }
+
+    #[test]
+    fn test_ray_transformed_camera() {
+        let mut camera = Camera::new(201, 101, PI / 2.);
+        camera.set_transform(
+            Matrix4::rotation(PI / 4., Rotation::Y) * Matrix4::translation(0., -2., 5.),
+        );
+        let ray = camera.ray_for_pixel(100, 50).unwrap();
+
+        assert_eq!(ray.origin.x.specific_round(5), 0.);
+        assert_eq!(ray.origin.y.specific_round(5), 2.);
+        assert_eq!(ray.origin.z.specific_round(5), -5.);
+        assert_eq!(
+            ray.direction.x.specific_round(5),
+            FRAC_1_SQRT_2.specific_round(5)
+        );
+        assert_eq!(ray.direction.y.specific_round(5), 0.);
+        assert_eq!(
+            ray.direction.z.specific_round(5),
+            -FRAC_1_SQRT_2.specific_round(5)
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let world = default_world();
+        let mut camera = Camera::new(11, 11, PI / 2.);
+        camera.set_transform(Matrix4::view_transform(
+            Point::new(0., 0., -5.),
+            Point::new(0., 0., 0.),
+            Vector::new(0., 1., 0.),
+        ));
+        let image = camera.render(&world).unwrap();
+        let color = image.pixel_at(5, 5);
+
+        assert_eq!(color.r.specific_round(5), 0.38066);
+        assert_eq!(color.g.specific_round(5), 0.47583);
+        assert_eq!(color.b.specific_round(5), 0.2855);
+    }
+
+    #[test]
+    fn test_sample_offsets_cover_pixel() {
+        assert_eq!(sample_offsets(1), vec![(0.5, 0.5)]);
+
+        let offsets = sample_offsets(4);
+        let mut xs: Vec<f64> = offsets.iter().map(|o| o.0).collect();
+        let mut ys: Vec<f64> = offsets.iter().map(|o| o.1).collect();
+        xs.sort_by(f64::total_cmp);
+        ys.sort_by(f64::total_cmp);
+        assert_eq!(xs, vec![0.125, 0.375, 0.625, 0.875]);
+        assert_eq!(ys, vec![0.125, 0.375, 0.625, 0.875]);
+    }
+
+    #[test]
+    fn test_render_samples() {
+        let world = default_world();
+        let mut camera = Camera::new(11, 11, PI / 2.);
+        camera.set_transform(Matrix4::view_transform(
+            Point::new(0., 0., -5.),
+            Point::new(0., 0., 0.),
+            Vector::new(0., 1., 0.),
+        ));
+
+        assert!(camera.render_samples(&world, 0).is_err());
+
+        // The center pixel lies entirely on the sphere, so extra samples
+        // only shift the shading slightly.
+        let image = camera.render_samples(&world, 4).unwrap();
+        let color = image.pixel_at(5, 5);
+        assert_eq!(color.r.specific_round(1), 0.4);
+        assert_eq!(color.g.specific_round(1), 0.5);
+    }
+
+    #[test]
+    fn test_singular_camera_transform() {
+        let mut camera = Camera::new(11, 11, PI / 2.);
+        camera.set_transform(Matrix4::scaling(1., 0., 1.));
+
+        assert!(camera.ray_for_pixel(5, 5).is_err());
+        assert!(camera.render(&default_world()).is_err());
+    }
+}
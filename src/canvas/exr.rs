@@ -0,0 +1,616 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::{color::Color, error::StingrayError};
+
+use super::Canvas;
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const TILED_FLAG: u32 = 0x200;
+const DEEP_FLAG: u32 = 0x800;
+const MULTIPART_FLAG: u32 = 0x1000;
+
+/// How each channel is stored in an OpenEXR file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExrPixelType {
+    #[default]
+    Half,
+    Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExrCompression {
+    None,
+    /// Zlib over blocks of 16 scanlines.
+    #[default]
+    Zip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExrOptions {
+    pub pixel_type: ExrPixelType,
+    pub compression: ExrCompression,
+}
+
+/// Pixel types by their number in the file format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PixelType {
+    Uint,
+    Half,
+    Float,
+}
+
+impl PixelType {
+    fn size(self) -> usize {
+        match self {
+            PixelType::Half => 2,
+            PixelType::Uint | PixelType::Float => 4,
+        }
+    }
+}
+
+impl From<ExrPixelType> for PixelType {
+    fn from(pixel_type: ExrPixelType) -> Self {
+        match pixel_type {
+            ExrPixelType::Half => PixelType::Half,
+            ExrPixelType::Float => PixelType::Float,
+        }
+    }
+}
+
+impl Canvas {
+    /// Encodes the canvas as a single part, scanline OpenEXR image with
+    /// B, G and R channels holding the unclamped linear values.
+    pub fn to_exr(&self, options: ExrOptions) -> Vec<u8> {
+        let pixel_type = PixelType::from(options.pixel_type);
+        let (compression, lines_per_block) = match options.compression {
+            ExrCompression::None => (0u8, 1),
+            ExrCompression::Zip => (3u8, 16),
+        };
+        let (max_x, max_y) = (self.width as i32 - 1, self.height as i32 - 1);
+
+        let mut exr = MAGIC.to_vec();
+        exr.extend_from_slice(&2u32.to_le_bytes());
+
+        let mut channels = vec![];
+        for name in ["B", "G", "R"] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&(pixel_type as i32).to_le_bytes());
+            // Perceptually linear flag, three reserved bytes, x and y sampling
+            channels.extend_from_slice(&[0, 0, 0, 0]);
+            channels.extend_from_slice(&1i32.to_le_bytes());
+            channels.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channels.push(0);
+        let window: Vec<u8> = [0, 0, max_x, max_y]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+
+        attribute(&mut exr, "channels", "chlist", &channels);
+        attribute(&mut exr, "compression", "compression", &[compression]);
+        attribute(&mut exr, "dataWindow", "box2i", &window);
+        attribute(&mut exr, "displayWindow", "box2i", &window);
+        attribute(&mut exr, "lineOrder", "lineOrder", &[0]);
+        attribute(&mut exr, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+        attribute(&mut exr, "screenWindowCenter", "v2f", &[0; 8]);
+        attribute(&mut exr, "screenWindowWidth", "float", &1f32.to_le_bytes());
+        exr.push(0);
+
+        let rows: Vec<&[Color]> = self.pixels.chunks(self.width.max(1) as usize).collect();
+        let blocks: Vec<&[&[Color]]> = rows.chunks(lines_per_block).collect();
+        let table_start = exr.len();
+        exr.resize(table_start + blocks.len() * 8, 0);
+
+        for (index, block) in blocks.iter().enumerate() {
+            let offset = exr.len() as u64;
+            exr[table_start + index * 8..][..8].copy_from_slice(&offset.to_le_bytes());
+
+            let mut raw = vec![];
+            for row in block.iter() {
+                for channel in 0..3 {
+                    for pixel in row.iter() {
+                        let value = [pixel.b, pixel.g, pixel.r][channel] as f32;
+                        match pixel_type {
+                            PixelType::Half => raw.extend(f32_to_half(value).to_le_bytes()),
+                            _ => raw.extend(value.to_le_bytes()),
+                        }
+                    }
+                }
+            }
+            let data = match options.compression {
+                ExrCompression::None => raw,
+                ExrCompression::Zip => {
+                    let compressed = zip_compress(&raw);
+                    // Blocks that do not shrink are stored as they are
+                    if compressed.len() < raw.len() {
+                        compressed
+                    } else {
+                        raw
+                    }
+                }
+            };
+
+            let y = (index * lines_per_block) as i32;
+            exr.extend_from_slice(&y.to_le_bytes());
+            exr.extend_from_slice(&(data.len() as i32).to_le_bytes());
+            exr.extend_from_slice(&data);
+        }
+        exr
+    }
+
+    pub fn save_exr(&self, path: &str, options: ExrOptions) -> io::Result<()> {
+        fs::write(path, self.to_exr(options))
+    }
+
+    /// Decodes a single part, scanline OpenEXR image stored uncompressed or
+    /// with ZIP/ZIPS compression. Reads R, G and B channels of any pixel
+    /// type, or a lone Y channel as grey; other channels are skipped.
+    pub fn from_exr(data: &[u8]) -> Result<Canvas, StingrayError> {
+        let mut reader = Reader { data, pos: 0 };
+        if reader.bytes(4)? != MAGIC {
+            return Err(StingrayError::Parse("not an OpenEXR file".to_string()));
+        }
+        let version = reader.u32()?;
+        if version & 0xff != 2 {
+            return Err(StingrayError::Unsupported(format!(
+                "unsupported OpenEXR version {}",
+                version & 0xff
+            )));
+        }
+        if version & (TILED_FLAG | DEEP_FLAG | MULTIPART_FLAG) != 0 {
+            return Err(StingrayError::Unsupported(
+                "only single part scanline images are supported".to_string(),
+            ));
+        }
+
+        let mut channels = None;
+        let mut compression = None;
+        let mut window = None;
+        loop {
+            let name = reader.name()?;
+            if name.is_empty() {
+                break;
+            }
+            let kind = reader.name()?;
+            let size = reader.i32()?;
+            let value =
+                reader
+                    .bytes(usize::try_from(size).map_err(|_| {
+                        StingrayError::Parse("negative attribute size".to_string())
+                    })?)?;
+            let mut value = Reader {
+                data: value,
+                pos: 0,
+            };
+            match (name, kind) {
+                ("channels", "chlist") => channels = Some(parse_channels(&mut value)?),
+                ("compression", "compression") => compression = Some(value.bytes(1)?[0]),
+                ("dataWindow", "box2i") => {
+                    window = Some([value.i32()?, value.i32()?, value.i32()?, value.i32()?])
+                }
+                _ => {}
+            }
+        }
+
+        let channels = channels.ok_or(StingrayError::Parse(
+            "missing channels attribute".to_string(),
+        ))?;
+        let [min_x, min_y, max_x, max_y] = window.ok_or(StingrayError::Parse(
+            "missing dataWindow attribute".to_string(),
+        ))?;
+        let compression = compression.ok_or(StingrayError::Parse(
+            "missing compression attribute".to_string(),
+        ))?;
+        let lines_per_block = match compression {
+            0 | 2 => 1,
+            3 => 16,
+            other => {
+                return Err(StingrayError::Unsupported(format!(
+                    "unsupported compression method {other}"
+                )))
+            }
+        };
+        if max_x < min_x || max_y < min_y {
+            return Err(StingrayError::Parse("empty data window".to_string()));
+        }
+        let (width, height) = (
+            i64::from(max_x) - i64::from(min_x) + 1,
+            i64::from(max_y) - i64::from(min_y) + 1,
+        );
+        let too_large = || {
+            StingrayError::Parse(format!(
+                "data window of {width}x{height} pixels is too large"
+            ))
+        };
+        let width = u32::try_from(width).map_err(|_| too_large())? as usize;
+        let height = u32::try_from(height).map_err(|_| too_large())? as usize;
+
+        let index = |name: &str| channels.iter().position(|(n, _)| n == name);
+        let sources = match (index("R"), index("G"), index("B"), index("Y")) {
+            (Some(r), Some(g), Some(b), _) => [r, g, b],
+            (_, _, _, Some(y)) => [y, y, y],
+            _ => {
+                return Err(StingrayError::Parse(
+                    "image has no RGB or Y channels".to_string(),
+                ))
+            }
+        };
+        let line_size = channels
+            .iter()
+            .map(|(_, t)| t.size())
+            .sum::<usize>()
+            .checked_mul(width)
+            .ok_or_else(too_large)?;
+        // Deflate packs at most about 1032 bytes into one, so a window
+        // bigger than that is corrupt and not worth allocating
+        let ratio = if compression == 0 { 1 } else { 1032 };
+        let image_size = line_size.checked_mul(height).ok_or_else(too_large)?;
+        if image_size > (data.len() - reader.pos).saturating_mul(ratio) {
+            return Err(too_large());
+        }
+
+        let blocks = height.div_ceil(lines_per_block);
+        let offsets = (0..blocks)
+            .map(|_| reader.u64())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut values = vec![[0f32; 3]; width * height];
+        for offset in offsets {
+            reader.pos = usize::try_from(offset)
+                .map_err(|_| StingrayError::Parse("bad block offset".to_string()))?;
+            let y = reader.i32()?;
+            let size = reader.i32()?;
+            let packed = reader.bytes(
+                usize::try_from(size)
+                    .map_err(|_| StingrayError::Parse("negative block size".to_string()))?,
+            )?;
+            if y < min_y || y > max_y {
+                return Err(StingrayError::Parse(format!(
+                    "block starts outside the data window at y = {y}"
+                )));
+            }
+            // In i64, as the window can span more than i32::MAX rows
+            let first = (i64::from(y) - i64::from(min_y)) as usize;
+            let lines = lines_per_block.min(height - first);
+            let expected = lines * line_size;
+            let block = if packed.len() < expected {
+                zip_decompress(packed, expected)?
+            } else {
+                packed.to_vec()
+            };
+
+            for (line, bytes) in block.chunks(line_size).take(lines).enumerate() {
+                let mut samples = Reader {
+                    data: bytes,
+                    pos: 0,
+                };
+                let row = &mut values[(first + line) * width..][..width];
+                for (channel, (_, pixel_type)) in channels.iter().enumerate() {
+                    for pixel in row.iter_mut() {
+                        let value = samples.sample(*pixel_type)?;
+                        for (target, &source) in sources.iter().enumerate() {
+                            if source == channel {
+                                pixel[target] = value;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Canvas {
+            width: width as u32,
+            height: height as u32,
+            pixels: values
+                .iter()
+                .map(|[r, g, b]| Color::new(*r as f64, *g as f64, *b as f64))
+                .collect(),
+        })
+    }
+
+    pub fn load_exr(path: &str) -> Result<Canvas, StingrayError> {
+        let data = fs::read(path).map_err(|source| StingrayError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Canvas::from_exr(&data).map_err(|e| e.context(path))
+    }
+}
+
+fn attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+fn parse_channels(reader: &mut Reader) -> Result<Vec<(String, PixelType)>, StingrayError> {
+    let mut channels = vec![];
+    loop {
+        let name = reader.name()?;
+        if name.is_empty() {
+            return Ok(channels);
+        }
+        let pixel_type = match reader.i32()? {
+            0 => PixelType::Uint,
+            1 => PixelType::Half,
+            2 => PixelType::Float,
+            other => {
+                return Err(StingrayError::Parse(format!(
+                    "channel {name}: unknown pixel type {other}"
+                )))
+            }
+        };
+        reader.bytes(4)?;
+        if (reader.i32()?, reader.i32()?) != (1, 1) {
+            return Err(StingrayError::Parse(format!(
+                "channel {name}: subsampled channels are not supported"
+            )));
+        }
+        channels.push((name.to_string(), pixel_type));
+    }
+}
+
+/// ZIP blocks split the bytes into even and odd halves and store each byte
+/// as the difference from the one before, which zlib compresses better.
+fn zip_compress(raw: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = raw
+        .iter()
+        .step_by(2)
+        .chain(raw.iter().skip(1).step_by(2))
+        .copied()
+        .collect();
+    for i in (1..bytes.len()).rev() {
+        bytes[i] = bytes[i].wrapping_sub(bytes[i - 1]).wrapping_add(128);
+    }
+
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder
+        .write_all(&bytes)
+        .and_then(|_| encoder.finish())
+        .expect("compressing into memory cannot fail")
+}
+
+fn zip_decompress(packed: &[u8], expected: usize) -> Result<Vec<u8>, StingrayError> {
+    let mut bytes = Vec::with_capacity(expected);
+    // One byte past the expected size is enough to tell it is wrong
+    ZlibDecoder::new(packed)
+        .take(expected as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| StingrayError::Parse(format!("corrupt ZIP block: {e}")))?;
+    if bytes.len() != expected {
+        return Err(StingrayError::Parse(
+            "ZIP block has the wrong size".to_string(),
+        ));
+    }
+    for i in 1..bytes.len() {
+        bytes[i] = bytes[i - 1].wrapping_add(bytes[i]).wrapping_sub(128);
+    }
+
+    let (even, odd) = bytes.split_at(expected.div_ceil(2));
+    let mut raw = Vec::with_capacity(expected);
+    for i in 0..expected {
+        raw.push(if i % 2 == 0 { even[i / 2] } else { odd[i / 2] });
+    }
+    Ok(raw)
+}
+
+/// Rounds to the nearest half precision float, ties to even.
+fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent < -10 {
+        return sign;
+    }
+    let (significand, shift, base) = if exponent <= 0 {
+        // Subnormal: the implicit leading bit becomes part of the mantissa
+        (mantissa | 0x80_0000, (14 - exponent) as u32, 0)
+    } else {
+        (mantissa, 13, (exponent as u32) << 10)
+    };
+    let half = base | (significand >> shift);
+    let remainder = significand & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round_up = remainder > halfway || (remainder == halfway && half & 1 == 1);
+    // A carry out of the mantissa correctly bumps the exponent
+    sign | (half + round_up as u32) as u16
+}
+
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1. } else { 1. };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0. => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1. + mantissa / 1024.) * 2f32.powi(exponent - 15),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], StingrayError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos.saturating_add(count))
+            .ok_or(StingrayError::Parse("unexpected end of file".to_string()))?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, StingrayError> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, StingrayError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, StingrayError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// A null terminated attribute, type or channel name.
+    fn name(&mut self) -> Result<&'a str, StingrayError> {
+        let end = self.data[self.pos..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(StingrayError::Parse("unterminated name".to_string()))?;
+        let name = std::str::from_utf8(self.bytes(end)?)
+            .map_err(|_| StingrayError::Parse("invalid name bytes".to_string()))?;
+        self.pos += 1;
+        Ok(name)
+    }
+
+    fn sample(&mut self, pixel_type: PixelType) -> Result<f32, StingrayError> {
+        let bytes = self.bytes(pixel_type.size())?;
+        Ok(match pixel_type {
+            PixelType::Uint => u32::from_le_bytes(bytes.try_into().unwrap()) as f32,
+            PixelType::Half => half_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])),
+            PixelType::Float => f32::from_le_bytes(bytes.try_into().unwrap()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hdr_canvas(width: u32, height: u32) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = Color::new(x as f64 * 0.75, 8.5, -(y as f64) * 0.125);
+                canvas.set_pixel(x, y, color);
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn test_half_conversion() {
+        assert_eq!(f32_to_half(1.), 0x3c00);
+        assert_eq!(f32_to_half(-2.), 0xc000);
+        assert_eq!(f32_to_half(0.5), 0x3800);
+        assert_eq!(f32_to_half(65504.), 0x7bff);
+        assert_eq!(f32_to_half(1e6), 0x7c00);
+        assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(1e-10), 0);
+        // 1 + 2^-11 sits halfway between two halves and rounds to even
+        assert_eq!(f32_to_half(1. + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_half(1. + 3. * 2f32.powi(-11)), 0x3c02);
+
+        for half in [0x3c00, 0xc000, 0x0001, 0x03ff, 0x7bff, 0x3555] {
+            assert_eq!(f32_to_half(half_to_f32(half)), half);
+        }
+        assert!(half_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn test_exr_round_trip() {
+        // Taller than one ZIP block, so the last block is partial
+        let canvas = hdr_canvas(7, 19);
+        for pixel_type in [ExrPixelType::Half, ExrPixelType::Float] {
+            for compression in [ExrCompression::None, ExrCompression::Zip] {
+                let options = ExrOptions {
+                    pixel_type,
+                    compression,
+                };
+                let decoded = Canvas::from_exr(&canvas.to_exr(options)).unwrap();
+
+                assert_eq!((decoded.width, decoded.height), (7, 19));
+                for (left, right) in decoded.pixels.iter().zip(canvas.pixels.iter()) {
+                    assert_eq!((left.r, left.g, left.b), (right.r, right.g, right.b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exr_zip_compresses() {
+        let mut canvas = Canvas::new(64, 64);
+        canvas.pixels.fill(Color::new(0.25, 0.5, 4.));
+        let plain = canvas.to_exr(ExrOptions {
+            compression: ExrCompression::None,
+            ..Default::default()
+        });
+        let zipped = canvas.to_exr(ExrOptions::default());
+
+        assert!(zipped.len() * 10 < plain.len());
+    }
+
+    #[test]
+    fn test_exr_header() {
+        let exr = Canvas::new(4, 2).to_exr(ExrOptions::default());
+        let header = String::from_utf8_lossy(&exr);
+
+        assert_eq!(exr[..4], MAGIC);
+        for name in [
+            "channels",
+            "compression",
+            "dataWindow",
+            "displayWindow",
+            "lineOrder",
+        ] {
+            assert!(header.contains(name), "missing {name}");
+        }
+    }
+
+    #[test]
+    fn test_from_exr_errors() {
+        let exr = hdr_canvas(3, 3).to_exr(ExrOptions::default());
+
+        assert!(Canvas::from_exr(b"P6\n1 1\n255\n").is_err());
+        assert!(Canvas::from_exr(&exr[..exr.len() - 4]).is_err());
+
+        let mut tiled = exr.clone();
+        tiled[5] |= (TILED_FLAG >> 8) as u8;
+        assert!(Canvas::from_exr(&tiled).is_err());
+    }
+
+    #[test]
+    fn test_from_exr_huge_data_window() {
+        let exr = hdr_canvas(3, 3).to_exr(ExrOptions::default());
+        let start = exr
+            .windows(10)
+            .position(|name| name == b"dataWindow")
+            .unwrap();
+        let window = start + b"dataWindow\0box2i\0".len() + 4;
+
+        for (min, max) in [(i32::MIN, i32::MAX), (0, 1 << 20)] {
+            let mut huge = exr.clone();
+            huge[window..window + 4].copy_from_slice(&min.to_le_bytes());
+            huge[window + 4..window + 8].copy_from_slice(&min.to_le_bytes());
+            huge[window + 8..window + 12].copy_from_slice(&max.to_le_bytes());
+            huge[window + 12..window + 16].copy_from_slice(&max.to_le_bytes());
+            assert!(matches!(
+                Canvas::from_exr(&huge),
+                Err(StingrayError::Parse(_))
+            ));
+        }
+    }
+}
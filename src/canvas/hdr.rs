@@ -0,0 +1,398 @@
+use std::{fs, io};
+
+use crate::{color::Color, error::StingrayError};
+
+use super::Canvas;
+
+/// Scanlines this wide can use run length encoding.
+const RLE_WIDTHS: std::ops::RangeInclusive<u32> = 8..=0x7fff;
+
+impl Canvas {
+    /// Encodes the canvas as a Radiance RGBE (.hdr) image. Channels keep
+    /// their full range; only negative values are clamped to zero.
+    pub fn to_hdr(&self) -> Vec<u8> {
+        let mut hdr = format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )
+        .into_bytes();
+
+        for row in self.pixels.chunks(self.width.max(1) as usize) {
+            let rgbe: Vec<[u8; 4]> = row.iter().map(encode_rgbe).collect();
+            if !RLE_WIDTHS.contains(&self.width) {
+                hdr.extend(rgbe.iter().flatten());
+                continue;
+            }
+            hdr.extend_from_slice(&[2, 2, (self.width >> 8) as u8, self.width as u8]);
+            for component in 0..4 {
+                let values: Vec<u8> = rgbe.iter().map(|pixel| pixel[component]).collect();
+                encode_runs(&values, &mut hdr);
+            }
+        }
+        hdr
+    }
+
+    pub fn save_hdr(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_hdr())
+    }
+
+    /// Decodes a Radiance RGBE image in flat, old or new run length
+    /// encoding, undoing any `EXPOSURE` recorded in the header.
+    pub fn from_hdr(data: &[u8]) -> Result<Canvas, StingrayError> {
+        let mut pos = 0;
+        let mut line = || -> Result<&str, StingrayError> {
+            let end = data[pos..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .ok_or(StingrayError::Parse("unexpected end of header".to_string()))?;
+            let line = std::str::from_utf8(&data[pos..pos + end])
+                .map_err(|_| StingrayError::Parse("invalid header bytes".to_string()))?;
+            pos += end + 1;
+            Ok(line)
+        };
+
+        if !line()?.starts_with("#?") {
+            return Err(StingrayError::Parse("missing '#?' signature".to_string()));
+        }
+        let mut exposure = 1.;
+        loop {
+            let header = line()?;
+            if header.is_empty() {
+                break;
+            }
+            if let Some(format) = header.strip_prefix("FORMAT=") {
+                if format.trim() != "32-bit_rle_rgbe" {
+                    return Err(StingrayError::Unsupported(format!(
+                        "unsupported pixel format '{}'",
+                        format.trim()
+                    )));
+                }
+            } else if let Some(value) = header.strip_prefix("EXPOSURE=") {
+                exposure *= value.trim().parse::<f64>().map_err(|_| {
+                    StingrayError::Parse(format!("invalid exposure '{}'", value.trim()))
+                })?;
+            }
+        }
+
+        let resolution = line()?;
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (height.parse::<u32>(), width.parse::<u32>()),
+            _ => {
+                return Err(StingrayError::Unsupported(format!(
+                    "unsupported resolution line '{resolution}'"
+                )))
+            }
+        };
+        let (height, width) = match (height, width) {
+            (Ok(height), Ok(width)) => (height, width),
+            _ => {
+                return Err(StingrayError::Parse(format!(
+                    "invalid resolution line '{resolution}'"
+                )))
+            }
+        };
+
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .ok_or(StingrayError::Parse(format!(
+                "image of {width}x{height} pixels is too large"
+            )))?;
+        let mut reader = Scanlines {
+            data,
+            pos,
+            width: width as usize,
+        };
+        // Reserve no more than the data could hold, even fully run length
+        // encoded, so a lying header cannot exhaust memory up front
+        let mut pixels = Vec::with_capacity(count.min(data.len() - pos));
+        for _ in 0..height {
+            for rgbe in reader.scanline()? {
+                pixels.push(decode_rgbe(rgbe) * (1. / exposure));
+            }
+        }
+        Ok(Canvas {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn load_hdr(path: &str) -> Result<Canvas, StingrayError> {
+        let data = fs::read(path).map_err(|source| StingrayError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Canvas::from_hdr(&data).map_err(|e| e.context(path))
+    }
+}
+
+/// Shares one exponent between the three channels, so the brightest keeps
+/// eight bits of precision.
+fn encode_rgbe(color: &Color) -> [u8; 4] {
+    let (r, g, b) = (color.r.max(0.), color.g.max(0.), color.b.max(0.));
+    let brightest = r.max(g).max(b);
+    if brightest < 1e-32 {
+        return [0; 4];
+    }
+    let mut exponent = brightest.log2().floor() as i32 + 1;
+    // log2 can land a hair off at exact powers of two
+    if brightest / 2f64.powi(exponent) >= 1. {
+        exponent += 1;
+    }
+    let scale = 256. / 2f64.powi(exponent);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+fn decode_rgbe([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::black();
+    }
+    let scale = 2f64.powi(e as i32 - (128 + 8));
+    Color::new(r as f64 * scale, g as f64 * scale, b as f64 * scale)
+}
+
+/// Writes one component of a scanline as runs of a repeated byte
+/// (count + 128) and literal dumps (count up to 128).
+fn encode_runs(values: &[u8], out: &mut Vec<u8>) {
+    let run_length = |start: usize| {
+        values[start..]
+            .iter()
+            .take(127)
+            .take_while(|&&value| value == values[start])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < values.len() {
+        let run = run_length(i);
+        if run >= 3 {
+            out.extend_from_slice(&[128 + run as u8, values[i]]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < values.len() && i - start < 128 && run_length(i) < 3 {
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend_from_slice(&values[start..i]);
+    }
+}
+
+struct Scanlines<'a> {
+    data: &'a [u8],
+    pos: usize,
+    width: usize,
+}
+
+impl Scanlines<'_> {
+    fn byte(&mut self) -> Result<u8, StingrayError> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or(StingrayError::Parse("truncated pixel data".to_string()))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn pixel(&mut self) -> Result<[u8; 4], StingrayError> {
+        Ok([self.byte()?, self.byte()?, self.byte()?, self.byte()?])
+    }
+
+    fn scanline(&mut self) -> Result<Vec<[u8; 4]>, StingrayError> {
+        let start = self.pos;
+        let first = self.pixel()?;
+        let is_rle = first[0] == 2
+            && first[1] == 2
+            && first[2] & 0x80 == 0
+            && RLE_WIDTHS.contains(&(self.width as u32));
+        if !is_rle {
+            self.pos = start;
+            return self.flat_scanline();
+        }
+        if ((first[2] as usize) << 8 | first[3] as usize) != self.width {
+            return Err(StingrayError::Parse(
+                "scanline width does not match the image".to_string(),
+            ));
+        }
+
+        let mut scanline = vec![[0; 4]; self.width];
+        for component in 0..4 {
+            let mut x = 0;
+            while x < self.width {
+                let count = self.byte()? as usize;
+                let (count, run) = if count > 128 {
+                    (count - 128, Some(self.byte()?))
+                } else {
+                    (count, None)
+                };
+                if count == 0 || x + count > self.width {
+                    return Err(StingrayError::Parse(
+                        "bad run length in scanline".to_string(),
+                    ));
+                }
+                for pixel in scanline[x..x + count].iter_mut() {
+                    pixel[component] = match run {
+                        Some(value) => value,
+                        None => self.byte()?,
+                    };
+                }
+                x += count;
+            }
+        }
+        Ok(scanline)
+    }
+
+    /// Plain RGBE pixels, where (1, 1, 1, n) repeats the previous pixel in
+    /// the original Radiance run length scheme.
+    fn flat_scanline(&mut self) -> Result<Vec<[u8; 4]>, StingrayError> {
+        let remaining = self.data.len() - self.pos;
+        let mut scanline: Vec<[u8; 4]> = Vec::with_capacity(self.width.min(remaining / 4));
+        let mut shift = 0;
+        while scanline.len() < self.width {
+            let pixel = self.pixel()?;
+            match (pixel, scanline.last().copied()) {
+                ([1, 1, 1, count], Some(previous)) => {
+                    // Four markers in a row already reach 2^32 pixels
+                    if count == 0 || shift > 24 {
+                        return Err(StingrayError::Parse(
+                            "bad run length in scanline".to_string(),
+                        ));
+                    }
+                    let count = (count as usize) << shift;
+                    if count > self.width - scanline.len() {
+                        return Err(StingrayError::Parse(
+                            "bad run length in scanline".to_string(),
+                        ));
+                    }
+                    scanline.extend(std::iter::repeat_n(previous, count));
+                    shift += 8;
+                }
+                _ => {
+                    scanline.push(pixel);
+                    shift = 0;
+                }
+            }
+        }
+        Ok(scanline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn gradient(width: u32, height: u32) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let t = x as f64 / width as f64;
+                canvas.set_pixel(x, y, Color::new(t * 40., 0.25, y as f64 * 0.01));
+            }
+        }
+        canvas
+    }
+
+    fn assert_close(left: Color, right: Color) {
+        // RGBE keeps about eight bits relative to the brightest channel
+        let tolerance = right.r.max(right.g).max(right.b) / 100. + 1e-9;
+        for (l, r) in [(left.r, right.r), (left.g, right.g), (left.b, right.b)] {
+            assert!((l - r).abs() <= tolerance, "{left:?} != {right:?}");
+        }
+    }
+
+    #[test]
+    fn test_rgbe_encoding() {
+        assert_eq!(encode_rgbe(&Color::new(1., 0.5, 0.)), [128, 64, 0, 129]);
+        assert_eq!(encode_rgbe(&Color::black()), [0; 4]);
+        assert_eq!(encode_rgbe(&Color::new(-3., 0., 0.)), [0; 4]);
+
+        let color = decode_rgbe(encode_rgbe(&Color::new(1000., 2.5, 0.125)));
+        assert_eq!(color.r, 1000.);
+        assert_eq!(color.b, 0.);
+    }
+
+    #[test]
+    fn test_hdr_header() {
+        let hdr = Canvas::new(3, 2).to_hdr();
+        let text = String::from_utf8_lossy(&hdr);
+
+        assert!(text.starts_with("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n"));
+    }
+
+    #[test]
+    fn test_hdr_round_trip() {
+        // Narrow images are written flat, wider ones run length encoded
+        for canvas in [gradient(5, 3), gradient(64, 4)] {
+            let decoded = Canvas::from_hdr(&canvas.to_hdr()).unwrap();
+
+            assert_eq!(
+                (decoded.width, decoded.height),
+                (canvas.width, canvas.height)
+            );
+            for (left, right) in decoded.pixels.iter().zip(canvas.pixels.iter()) {
+                assert_close(*left, *right);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hdr_run_length_encoding() {
+        let mut canvas = Canvas::new(200, 1);
+        canvas.pixels.fill(Color::new(2., 2., 2.));
+        let hdr = canvas.to_hdr();
+
+        // Four components of two runs each, after the header and marker
+        assert!(hdr.len() < 60 + 4 + 4 * 4);
+        assert_close(
+            Canvas::from_hdr(&hdr).unwrap().pixel_at(199, 0),
+            Color::new(2., 2., 2.),
+        );
+    }
+
+    #[test]
+    fn test_from_hdr_old_rle_and_exposure() {
+        let mut hdr = b"#?RGBE\nEXPOSURE=2\n\n-Y 1 +X 4\n".to_vec();
+        hdr.extend_from_slice(&[128, 64, 0, 129, 1, 1, 1, 3]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+
+        assert_eq!(canvas.pixels.len(), 4);
+        let color = canvas.pixel_at(3, 0);
+        assert_eq!(color.r.specific_round(2), 0.5);
+        assert_eq!(color.g.specific_round(2), 0.25);
+    }
+
+    #[test]
+    fn test_from_hdr_errors() {
+        assert!(Canvas::from_hdr(b"P6\n").is_err());
+        assert!(
+            Canvas::from_hdr(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0").is_err()
+        );
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 1 +X 2\n\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn test_from_hdr_hostile_sizes() {
+        let huge = Canvas::from_hdr(b"#?RADIANCE\n\n-Y 200000 +X 200000\n\0\0\0\0");
+        assert!(matches!(huge, Err(StingrayError::Parse(_))));
+
+        // Empty repeat markers never fill the line, and eight in a row
+        // would shift the count past 64 bits
+        let mut markers = b"#?RADIANCE\n\n-Y 1 +X 4\n".to_vec();
+        markers.extend_from_slice(&[128, 64, 0, 129]);
+        for _ in 0..8 {
+            markers.extend_from_slice(&[1, 1, 1, 0]);
+        }
+        assert!(matches!(
+            Canvas::from_hdr(&markers),
+            Err(StingrayError::Parse(_))
+        ));
+    }
+}
@@ -1,11 +1,22 @@
+mod exr;
+mod hdr;
+mod png;
+mod ppm;
 pub mod scene_object;
-use crate::{color::Color, geo::point::Point, lighting::PointLight};
+mod tone;
+
+pub use self::{
+    exr::{ExrCompression, ExrOptions, ExrPixelType},
+    png::PngDepth,
+    tone::ToneOperator,
+};
+
+use crate::{color::Color, geo::point::Point};
 
 pub struct Canvas {
     pub width: u32,
     pub height: u32,
     pub pixels: Vec<Color>,
-    pub lights: Vec<PointLight>,
 }
 
 impl Canvas {
@@ -14,7 +25,6 @@ impl Canvas {
             width,
             height,
             pixels: vec![Color::black(); width as usize * height as usize],
-            lights: vec![],
         }
     }
 
@@ -25,29 +35,14 @@ impl Canvas {
         self.pixels[(y * self.width + x) as usize] = color;
     }
 
-    pub fn to_ppm(&self) -> String {
-        let mut ppm = String::from("P3\n");
-        ppm.push_str(&format!("{} {}\n", self.width, self.height));
-        ppm.push_str("255\n");
-        for pixel in self.pixels.iter() {
-            ppm.push_str(&format!("{} {} {}\n", pixel.r, pixel.g, pixel.b));
-        }
-        ppm
-    }
-
-    pub fn save_ppm(&self, path: &str) {
-        use std::fs::File;
-        use std::io::Write;
-        let mut file = File::create(path).unwrap();
-        file.write_all(self.to_ppm().as_bytes()).unwrap();
+    pub fn pixel_at(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
     }
 
     pub fn draw_point(&mut self, point: Point, color: Color) {
         let (x, y) = (point.x as u32, self.height - point.y as u32);
         self.set_pixel(x, y, color);
     }
-
-    pub fn add_light(&mut self, light: PointLight) {}
 }
 
 #[cfg(test)]
@@ -69,14 +64,12 @@ mod tests {
         let mut env = Environment::new(gravity, wind, position, velocity);
         while env.projectile.position.y > 0. {
             env.tick();
-            dbg!(&env.projectile.position);
             let (x, y) = (
                 env.projectile.position.x as u32,
                 env.projectile.position.y as u32,
             );
             let y = canvas.height - y;
-            let color = Color::new(255., 0., 0.);
-            dbg!(x, y);
+            let color = Color::new(1., 0., 0.);
             // circle around x,y
             let circle_points = vec![
                 (x - 1, y - 1),
@@ -86,12 +79,12 @@ mod tests {
                 (x, y),
             ];
             for point in circle_points {
-                let (x, y) = (point.0 as u32, point.1 as u32);
-                canvas.set_pixel(x, y, color.clone());
+                let (x, y) = (point.0, point.1);
+                canvas.set_pixel(x, y, color);
             }
         }
         assert!(env.projectile.position.y < 0.);
-        let path = "test.ppm";
-        canvas.save_ppm(path);
+        let path = std::env::temp_dir().join("stingray-projectile.ppm");
+        canvas.save_ppm(&path.to_string_lossy()).unwrap();
     }
 }
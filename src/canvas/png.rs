@@ -0,0 +1,118 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use png::{BitDepth, ColorType, Encoder, ScaledFloat, SrgbRenderingIntent};
+
+use super::Canvas;
+
+/// Bits per channel in an encoded PNG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PngDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+impl Canvas {
+    /// Encodes the canvas as an sRGB PNG. Pixels are treated as linear
+    /// light, so each channel is clamped and run through the sRGB transfer
+    /// curve before it is quantized.
+    pub fn write_png<W: Write>(&self, writer: W, depth: PngDepth) -> io::Result<()> {
+        let mut encoder = Encoder::new(writer, self.width, self.height);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+        // Fallback gamma for decoders that ignore the sRGB chunk
+        encoder.set_source_gamma(ScaledFloat::from_scaled(45455));
+
+        let srgb = self.pixels.iter().map(|pixel| pixel.to_srgb());
+        let data: Vec<u8> = match depth {
+            PngDepth::Eight => {
+                encoder.set_depth(BitDepth::Eight);
+                srgb.flat_map(|c| [c.r, c.g, c.b])
+                    .map(|c| (c * 255.).round() as u8)
+                    .collect()
+            }
+            PngDepth::Sixteen => {
+                encoder.set_depth(BitDepth::Sixteen);
+                srgb.flat_map(|c| [c.r, c.g, c.b])
+                    .flat_map(|c| ((c * 65535.).round() as u16).to_be_bytes())
+                    .collect()
+            }
+        };
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Saves the canvas as an 8-bit sRGB PNG.
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        self.save_png_with_depth(path, PngDepth::Eight)
+    }
+
+    pub fn save_png_with_depth(&self, path: &str, depth: PngDepth) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        self.write_png(file, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use png::Decoder;
+
+    use super::*;
+    use crate::color::Color;
+
+    fn encode(canvas: &Canvas, depth: PngDepth) -> (png::OutputInfo, Vec<u8>, bool) {
+        let mut data = vec![];
+        canvas.write_png(&mut data, depth).unwrap();
+
+        let mut reader = Decoder::new(data.as_slice()).read_info().unwrap();
+        let tagged_srgb = reader.info().srgb.is_some();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        pixels.truncate(info.buffer_size());
+        (info, pixels, tagged_srgb)
+    }
+
+    #[test]
+    fn test_png_eight_bit() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(0.5, 1.5, -0.5));
+        canvas.set_pixel(1, 0, Color::new(0.002, 0.214, 1.));
+        let (info, pixels, tagged_srgb) = encode(&canvas, PngDepth::Eight);
+
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.bit_depth, BitDepth::Eight);
+        assert_eq!(info.color_type, ColorType::Rgb);
+        assert!(tagged_srgb);
+        // Linear mid grey lands well above 128 once gamma encoded
+        assert_eq!(pixels, [188, 255, 0, 7, 127, 255]);
+    }
+
+    #[test]
+    fn test_png_sixteen_bit() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.set_pixel(0, 0, Color::new(1., 0.5, 0.));
+        let (info, pixels, _) = encode(&canvas, PngDepth::Sixteen);
+
+        assert_eq!(info.bit_depth, BitDepth::Sixteen);
+        let channels: Vec<u16> = pixels
+            .chunks(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(channels[0], 65535);
+        assert_eq!(channels[1], 48192);
+        assert_eq!(channels[2], 0);
+    }
+
+    #[test]
+    fn test_save_png_reports_io_errors() {
+        let canvas = Canvas::new(1, 1);
+
+        assert!(canvas.save_png("/nonexistent/dir/out.png").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use std::{fs, io};
+
+use crate::{color::Color, error::StingrayError};
+
+use super::Canvas;
+
+/// Longest line the plain PPM format allows.
+const MAX_LINE_LENGTH: usize = 70;
+
+impl Canvas {
+    /// Encodes the canvas as a plain (P3) PPM. Channels are scaled to
+    /// 0–255 and clamped, and every pixel row starts on a new line.
+    pub fn to_ppm(&self) -> String {
+        let mut ppm = format!("P3\n{} {}\n255\n", self.width, self.height);
+        for row in self.pixels.chunks(self.width.max(1) as usize) {
+            let mut line = String::new();
+            for value in row.iter().flat_map(|pixel| channels(pixel).into_iter()) {
+                let value = value.to_string();
+                if !line.is_empty() && line.len() + 1 + value.len() > MAX_LINE_LENGTH {
+                    ppm.push_str(&line);
+                    ppm.push('\n');
+                    line.clear();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&value);
+            }
+            ppm.push_str(&line);
+            ppm.push('\n');
+        }
+        ppm
+    }
+
+    /// Encodes the canvas as a binary (P6) PPM with one byte per channel.
+    pub fn to_ppm_binary(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.reserve(self.pixels.len() * 3);
+        for pixel in self.pixels.iter() {
+            ppm.extend_from_slice(&channels(pixel));
+        }
+        ppm
+    }
+
+    pub fn save_ppm(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_ppm())
+    }
+
+    /// Decodes a plain (P3) or binary (P6) PPM with any maxval, mapping
+    /// channels back to 0–1.
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, StingrayError> {
+        let mut reader = Reader { data, pos: 0 };
+        let magic = reader.token()?;
+        let binary = match magic {
+            "P3" => false,
+            "P6" => true,
+            _ => {
+                return Err(StingrayError::Unsupported(format!(
+                    "unsupported PPM format '{magic}'"
+                )))
+            }
+        };
+        let width = reader.number("width")?;
+        let height = reader.number("height")?;
+        let maxval = reader.number("maxval")?;
+        if maxval == 0 || maxval > u16::MAX as u32 {
+            return Err(StingrayError::Parse(format!(
+                "maxval must be between 1 and 65535, got {maxval}"
+            )));
+        }
+
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or(StingrayError::Parse(format!(
+                "image of {width}x{height} pixels is too large"
+            )))?;
+        let values = if binary {
+            // Exactly one whitespace byte separates the header from the raster
+            reader.pos += 1;
+            reader.raster(count, maxval > 255)?
+        } else {
+            (0..count)
+                .map(|_| reader.number("pixel value"))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if let Some(value) = values.iter().find(|&&value| value > maxval) {
+            return Err(StingrayError::Parse(format!(
+                "pixel value {value} exceeds maxval {maxval}"
+            )));
+        }
+
+        let scale = maxval as f64;
+        let pixels = values
+            .chunks(3)
+            .map(|c| {
+                Color::new(
+                    c[0] as f64 / scale,
+                    c[1] as f64 / scale,
+                    c[2] as f64 / scale,
+                )
+            })
+            .collect();
+        Ok(Canvas {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn load_ppm(path: &str) -> Result<Canvas, StingrayError> {
+        let data = fs::read(path).map_err(|source| StingrayError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Canvas::from_ppm(&data).map_err(|e| e.context(path))
+    }
+}
+
+/// The pixel's channels scaled to 0–255, rounded and clamped.
+fn channels(pixel: &Color) -> [u8; 3] {
+    let scale = |c: f64| (c * 255.).round().clamp(0., 255.) as u8;
+    [scale(pixel.r), scale(pixel.g), scale(pixel.b)]
+}
+
+/// Walks the whitespace separated tokens of a PPM file, skipping comments.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn token(&mut self) -> Result<&'a str, StingrayError> {
+        loop {
+            match self.data.get(self.pos) {
+                Some(b'#') => {
+                    while self.data.get(self.pos).is_some_and(|&b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => return Err(StingrayError::Parse("unexpected end of file".to_string())),
+            }
+        }
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#')
+        {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.pos])
+            .map_err(|_| StingrayError::Parse("invalid header bytes".to_string()))
+    }
+
+    fn number(&mut self, what: &str) -> Result<u32, StingrayError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| StingrayError::Parse(format!("expected {what}, got '{token}'")))
+    }
+
+    /// Reads `count` binary samples, big-endian when two bytes wide.
+    fn raster(&mut self, count: usize, wide: bool) -> Result<Vec<u32>, StingrayError> {
+        let truncated = || StingrayError::Parse("truncated pixel data".to_string());
+        let width = if wide { 2 } else { 1 };
+        let end = count
+            .checked_mul(width)
+            .and_then(|size| size.checked_add(self.pos))
+            .ok_or_else(truncated)?;
+        let bytes = self.data.get(self.pos..end).ok_or_else(truncated)?;
+        self.pos = end;
+        Ok(if wide {
+            bytes
+                .chunks(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as u32)
+                .collect()
+        } else {
+            bytes.iter().map(|&b| b as u32).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn lines(ppm: &str) -> Vec<&str> {
+        ppm.lines().collect()
+    }
+
+    #[test]
+    fn test_ppm_header() {
+        let canvas = Canvas::new(5, 3);
+
+        assert_eq!(lines(&canvas.to_ppm())[..3], ["P3", "5 3", "255"]);
+    }
+
+    #[test]
+    fn test_ppm_pixel_data() {
+        let mut canvas = Canvas::new(5, 3);
+        canvas.set_pixel(0, 0, Color::new(1.5, 0., 0.));
+        canvas.set_pixel(2, 1, Color::new(0., 0.5, 0.));
+        canvas.set_pixel(4, 2, Color::new(-0.5, 0., 1.));
+        let ppm = canvas.to_ppm();
+
+        assert_eq!(
+            lines(&ppm)[3..],
+            [
+                "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255",
+            ]
+        );
+        assert!(ppm.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_ppm_splits_long_lines() {
+        let mut canvas = Canvas::new(10, 2);
+        canvas.pixels.fill(Color::new(1., 0.8, 0.6));
+        let ppm = canvas.to_ppm();
+
+        assert_eq!(
+            lines(&ppm)[3..],
+            [
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+            ]
+        );
+        assert!(ppm.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+
+    #[test]
+    fn test_ppm_binary() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(1.5, 0.5, 0.));
+        canvas.set_pixel(1, 0, Color::new(0., 0., 1.));
+        let ppm = canvas.to_ppm_binary();
+
+        assert_eq!(&ppm[..11], b"P6\n2 1\n255\n");
+        assert_eq!(&ppm[11..], [255, 128, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.set_pixel(0, 0, Color::new(1., 0.2, 0.4));
+        canvas.set_pixel(2, 1, Color::new(0.6, 0.8, 0.));
+
+        for decoded in [
+            Canvas::from_ppm(canvas.to_ppm().as_bytes()).unwrap(),
+            Canvas::from_ppm(&canvas.to_ppm_binary()).unwrap(),
+        ] {
+            assert_eq!((decoded.width, decoded.height), (3, 2));
+            let color = decoded.pixel_at(2, 1);
+            assert_eq!(color.r.specific_round(2), 0.6);
+            assert_eq!(color.g.specific_round(2), 0.8);
+            assert_eq!(color.b, 0.);
+        }
+    }
+
+    #[test]
+    fn test_from_ppm_comments_and_maxval() {
+        let ppm = b"P3\n# made by hand\n2 1 # size\n100\n100 50 0\n# second pixel\n0 0 25\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+
+        let first = canvas.pixel_at(0, 0);
+        assert_eq!((first.r, first.g, first.b), (1., 0.5, 0.));
+        assert_eq!(canvas.pixel_at(1, 0).b, 0.25);
+    }
+
+    #[test]
+    fn test_from_ppm_wide_binary() {
+        let mut ppm = b"P6 1 1 65535\n".to_vec();
+        ppm.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        let color = Canvas::from_ppm(&ppm).unwrap().pixel_at(0, 0);
+
+        assert_eq!(color.r, 1.);
+        assert_eq!(color.g.specific_round(3), 0.5);
+        assert_eq!(color.b, 0.);
+    }
+
+    #[test]
+    fn test_from_ppm_errors() {
+        assert!(Canvas::from_ppm(b"P5\n1 1\n255\n\0").is_err());
+        assert!(Canvas::from_ppm(b"P3\n2 1\n255\n0 0 0\n").is_err());
+        assert!(Canvas::from_ppm(b"P3\n1 1\n255\n0 256 0\n").is_err());
+        assert!(Canvas::from_ppm(b"P3\n1 1\n0\n0 0 0\n").is_err());
+        assert!(Canvas::from_ppm(b"P6\n2 1\n255\n\0\0\0").is_err());
+        assert!(Canvas::from_ppm(b"P3\nwide 1\n255\n").is_err());
+        assert!(matches!(
+            Canvas::from_ppm(b"P6\n4294967295 4294967295\n255\n"),
+            Err(StingrayError::Parse(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(b"P6\n4294967295 1\n65535\n"),
+            Err(StingrayError::Parse(_))
+        ));
+    }
+}
@@ -12,15 +12,13 @@ impl<G: Geometry, T: Texture> SceneObject<G, T> {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         color::Color,
-        geo::{
-            matrix::Matrix, objects::geometry::Geometry, objects::sphere::Sphere, point::Point,
-            ray::Ray, vector::Vector,
-        },
-        lighting::{texture::Texture, Material, PointLight},
+        geo::{objects::sphere::Sphere, point::Point},
+        lighting::Material,
     };
 
     #[test]
@@ -29,5 +27,7 @@ mod tests {
         let color = Color::new(0.5, 0.5, 0.5);
         let material = Material::new(color, 0.5, 0.5, 0.5, 10.);
         let scene_object = SceneObject::new(sphere, material);
+
+        assert_eq!(scene_object.material.get_material().shininess, 10.);
     }
 }
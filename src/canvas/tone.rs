@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use crate::{color::Color, error::StingrayError};
+
+use super::Canvas;
+
+/// Curves that compress linear scene radiance into the 0–1 display range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneOperator {
+    /// Cuts everything above 1 off, as writing the raw values would.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white.
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic reference curve.
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2, white at 11.2.
+    Uncharted2,
+}
+
+impl ToneOperator {
+    /// Maps one linear channel to 0–1.
+    pub fn apply(self, value: f64) -> f64 {
+        let value = value.max(0.);
+        let mapped = match self {
+            ToneOperator::Clamp => value,
+            ToneOperator::Reinhard => value / (1. + value),
+            ToneOperator::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+            ToneOperator::Uncharted2 => {
+                const WHITE: f64 = 11.2;
+                const EXPOSURE_BIAS: f64 = 2.;
+                hable(value * EXPOSURE_BIAS) / hable(WHITE)
+            }
+        };
+        mapped.clamp(0., 1.)
+    }
+}
+
+impl FromStr for ToneOperator {
+    type Err = StingrayError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "clamp" | "none" => Ok(ToneOperator::Clamp),
+            "reinhard" => Ok(ToneOperator::Reinhard),
+            "aces" => Ok(ToneOperator::Aces),
+            "uncharted2" | "hable" => Ok(ToneOperator::Uncharted2),
+            _ => Err(StingrayError::Parse(format!(
+                "unknown tone operator '{name}', expected clamp, reinhard, aces or uncharted2"
+            ))),
+        }
+    }
+}
+
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15; // shoulder strength
+    const B: f64 = 0.50; // linear strength
+    const C: f64 = 0.10; // linear angle
+    const D: f64 = 0.20; // toe strength
+    const E: f64 = 0.02; // toe numerator
+    const F: f64 = 0.30; // toe denominator
+    (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+impl Canvas {
+    /// Scales the linear pixels by `exposure` stops and compresses them
+    /// into 0–1 with `operator`. The result is still linear; encode it with
+    /// [`Canvas::to_srgb`] before writing formats that expect display values.
+    pub fn tone_map(&self, operator: ToneOperator, exposure: f64) -> Canvas {
+        let scale = 2f64.powf(exposure);
+        self.map_pixels(|pixel| {
+            Color::new(
+                operator.apply(pixel.r * scale),
+                operator.apply(pixel.g * scale),
+                operator.apply(pixel.b * scale),
+            )
+        })
+    }
+
+    /// Applies the sRGB transfer curve to every pixel.
+    pub fn to_srgb(&self) -> Canvas {
+        self.map_pixels(|pixel| pixel.to_srgb())
+    }
+
+    fn map_pixels(&self, f: impl Fn(&Color) -> Color) -> Canvas {
+        Canvas {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(f).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    #[test]
+    fn test_clamp_and_reinhard() {
+        assert_eq!(ToneOperator::Clamp.apply(0.25), 0.25);
+        assert_eq!(ToneOperator::Clamp.apply(4.), 1.);
+        assert_eq!(ToneOperator::Clamp.apply(-1.), 0.);
+        assert_eq!(ToneOperator::Reinhard.apply(1.), 0.5);
+        assert_eq!(ToneOperator::Reinhard.apply(3.), 0.75);
+    }
+
+    #[test]
+    fn test_filmic_curves() {
+        assert_eq!(ToneOperator::Aces.apply(0.), 0.);
+        assert_eq!(ToneOperator::Aces.apply(0.18).specific_round(4), 0.2669);
+        assert_eq!(ToneOperator::Aces.apply(100.), 1.);
+
+        assert_eq!(ToneOperator::Uncharted2.apply(0.), 0.);
+        // The exposure bias puts white at half the white point
+        assert_eq!(ToneOperator::Uncharted2.apply(5.6).specific_round(6), 1.);
+        assert!(ToneOperator::Uncharted2.apply(0.5) < ToneOperator::Uncharted2.apply(1.));
+    }
+
+    #[test]
+    fn test_tone_operator_from_str() {
+        assert_eq!("ACES".parse::<ToneOperator>().unwrap(), ToneOperator::Aces);
+        assert_eq!(
+            "hable".parse::<ToneOperator>().unwrap(),
+            ToneOperator::Uncharted2
+        );
+        assert!("filmic".parse::<ToneOperator>().is_err());
+    }
+
+    #[test]
+    fn test_tone_map_exposure() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(0.25, 0.5, 2.));
+        canvas.set_pixel(1, 0, Color::new(1., 1., 1.));
+
+        let brighter = canvas.tone_map(ToneOperator::Clamp, 1.);
+        let color = brighter.pixel_at(0, 0);
+        assert_eq!((color.r, color.g, color.b), (0.5, 1., 1.));
+
+        let darker = canvas.tone_map(ToneOperator::Reinhard, -1.);
+        assert_eq!(darker.pixel_at(1, 0).g.specific_round(5), 0.33333);
+    }
+
+    #[test]
+    fn test_canvas_to_srgb() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.set_pixel(0, 0, Color::new(0.5, 0., 1.));
+        let color = canvas.to_srgb().pixel_at(0, 0);
+
+        assert_eq!(color.r.specific_round(4), 0.7354);
+        assert_eq!(color.g, 0.);
+        assert_eq!(color.b.specific_round(6), 1.);
+    }
+}
@@ -1,4 +1,6 @@
-use std::ops::{Add, Mul, Sub};
+mod spaces;
+
+use std::ops::{Add, Div, Mul, Sub};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
@@ -15,6 +17,29 @@ impl Color {
     pub fn black() -> Color {
         Color::new(0., 0., 0.)
     }
+
+    pub fn white() -> Color {
+        Color::new(1., 1., 1.)
+    }
+
+    /// Relative luminance of the linear color, weighted for sRGB primaries.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Clamps every channel to 0–1.
+    pub fn clamp(&self) -> Color {
+        Color::new(
+            self.r.clamp(0., 1.),
+            self.g.clamp(0., 1.),
+            self.b.clamp(0., 1.),
+        )
+    }
+
+    /// Blends linearly from `self` at `t = 0` to `other` at `t = 1`.
+    pub fn lerp(&self, other: Color, t: f64) -> Color {
+        *self + (other - *self) * t
+    }
 }
 
 impl Add for Color {
@@ -46,9 +71,9 @@ impl Mul<f64> for Color {
 
     fn mul(self, other: f64) -> Color {
         Color {
-            r: (self.r as f64 * other),
-            g: (self.g as f64 * other),
-            b: (self.b as f64 * other),
+            r: self.r * other,
+            g: self.g * other,
+            b: self.b * other,
         }
     }
 }
@@ -65,6 +90,30 @@ impl Mul for Color {
     }
 }
 
+impl Div<f64> for Color {
+    type Output = Color;
+
+    fn div(self, other: f64) -> Color {
+        Color {
+            r: self.r / other,
+            g: self.g / other,
+            b: self.b / other,
+        }
+    }
+}
+
+impl Div for Color {
+    type Output = Color;
+
+    fn div(self, other: Color) -> Color {
+        Color {
+            r: self.r / other.r,
+            g: self.g / other.g,
+            b: self.b / other.b,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{assert_approx_eq, traits::ApproxEq};
@@ -96,7 +145,6 @@ mod tests {
         let c1 = Color::new(1., 0.2, 0.4);
         let c2 = Color::new(0.9, 1., 0.1);
         let c3 = c1 * c2;
-        dbg!(&c3);
         assert_approx_eq!(c3.r, 0.9);
         assert_approx_eq!(c3.g, 0.2);
         assert_approx_eq!(c3.b, 0.04);
@@ -108,4 +156,24 @@ mod tests {
         let c2 = c1 * 0.5;
         assert_eq!(c2.r, 0.45);
     }
+
+    #[test]
+    fn test_div() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        assert_approx_eq!(c1 / 2., Color::new(0.45, 0.3, 0.375));
+        assert_approx_eq!(c1 / Color::new(0.9, 2., 0.25), Color::new(1., 0.3, 3.));
+    }
+
+    #[test]
+    fn test_luminance_clamp_lerp() {
+        assert_approx_eq!(Color::white().luminance(), 1.);
+        assert_eq!(Color::new(0., 1., 0.).luminance(), 0.7152);
+        assert_approx_eq!(Color::new(-0.5, 0.5, 1.5).clamp(), Color::new(0., 0.5, 1.));
+
+        let from = Color::new(0., 0.5, 1.);
+        let to = Color::new(1., 0.5, 0.);
+        assert_approx_eq!(from.lerp(to, 0.), from);
+        assert_approx_eq!(from.lerp(to, 0.25), Color::new(0.25, 0.5, 0.75));
+        assert_approx_eq!(from.lerp(to, 1.), to);
+    }
 }
@@ -0,0 +1,313 @@
+//! Conversions between the linear RGB values the renderer works in and the
+//! colour spaces artists and other tools use.
+//!
+//! HSV, HSL and hex codes describe sRGB encoded values, the way colour
+//! pickers show them, so they pass through the sRGB transfer curve on the
+//! way in and out. XYZ and Lab use the D65 white point of sRGB.
+
+use super::Color;
+use crate::error::StingrayError;
+
+/// Reference white for Lab, in XYZ.
+const D65_WHITE: (f64, f64, f64) = (0.95047, 1., 1.08883);
+
+impl Color {
+    /// Applies the sRGB transfer curve to each linear channel, clamping to
+    /// 0–1 first, for writing to 8 or 16 bit image formats.
+    pub fn to_srgb(&self) -> Color {
+        Color::new(
+            srgb_encode(self.r),
+            srgb_encode(self.g),
+            srgb_encode(self.b),
+        )
+    }
+
+    /// The linear color for sRGB encoded channels in 0–1.
+    pub fn from_srgb(r: f64, g: f64, b: f64) -> Color {
+        Color::new(srgb_decode(r), srgb_decode(g), srgb_decode(b))
+    }
+
+    /// The linear color for 8-bit sRGB channels.
+    pub fn from_srgb8(r: u8, g: u8, b: u8) -> Color {
+        Color::from_srgb(r as f64 / 255., g as f64 / 255., b as f64 / 255.)
+    }
+
+    /// Parses an sRGB hex code such as `#ff8800` or `f80`, with or without
+    /// the leading `#`, into a linear color.
+    pub fn from_hex(hex: &str) -> Result<Color, StingrayError> {
+        let digits = hex.trim().trim_start_matches('#');
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(StingrayError::Parse(format!("invalid hex color '{hex}'")));
+        }
+        let channel = |i: usize, len: usize| {
+            let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap();
+            // Shorthand digits repeat, so f means ff
+            if len == 1 {
+                value * 17
+            } else {
+                value
+            }
+        };
+        match digits.len() {
+            3 => Ok(Color::from_srgb8(
+                channel(0, 1),
+                channel(1, 1),
+                channel(2, 1),
+            )),
+            6 => Ok(Color::from_srgb8(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+            )),
+            _ => Err(StingrayError::Parse(format!(
+                "invalid hex color '{hex}', expected 3 or 6 digits"
+            ))),
+        }
+    }
+
+    /// The sRGB hex code for this color, clamped to the displayable range.
+    pub fn to_hex(&self) -> String {
+        let srgb = self.to_srgb();
+        let byte = |c: f64| (c * 255.).round() as u8;
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            byte(srgb.r),
+            byte(srgb.g),
+            byte(srgb.b)
+        )
+    }
+
+    /// Hue in degrees, saturation and value of the sRGB encoded color.
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let srgb = self.to_srgb();
+        let (max, min) = extremes(&srgb);
+        let saturation = if max > 0. { (max - min) / max } else { 0. };
+        (hue(&srgb), saturation, max)
+    }
+
+    /// The linear color for a hue in degrees and saturation and value in 0–1.
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Color {
+        let chroma = value * saturation;
+        from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Hue in degrees, saturation and lightness of the sRGB encoded color.
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let srgb = self.to_srgb();
+        let (max, min) = extremes(&srgb);
+        let lightness = (max + min) / 2.;
+        let saturation = if max == min {
+            0.
+        } else {
+            (max - min) / (1. - (2. * lightness - 1.).abs())
+        };
+        (hue(&srgb), saturation, lightness)
+    }
+
+    /// The linear color for a hue in degrees and saturation and lightness
+    /// in 0–1.
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Color {
+        let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+        from_hue(hue, chroma, lightness - chroma / 2.)
+    }
+
+    /// CIE 1931 XYZ coordinates of the linear color.
+    pub fn to_xyz(&self) -> (f64, f64, f64) {
+        (
+            0.4124564 * self.r + 0.3575761 * self.g + 0.1804375 * self.b,
+            0.2126729 * self.r + 0.7151522 * self.g + 0.0721750 * self.b,
+            0.0193339 * self.r + 0.1191920 * self.g + 0.9503041 * self.b,
+        )
+    }
+
+    pub fn from_xyz(x: f64, y: f64, z: f64) -> Color {
+        Color::new(
+            3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+            -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+            0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+        )
+    }
+
+    /// CIE L*a*b* coordinates, with L from 0 for black to 100 for white.
+    pub fn to_lab(&self) -> (f64, f64, f64) {
+        let (x, y, z) = self.to_xyz();
+        let (xn, yn, zn) = D65_WHITE;
+        let (fx, fy, fz) = (lab_f(x / xn), lab_f(y / yn), lab_f(z / zn));
+        (116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz))
+    }
+
+    pub fn from_lab(l: f64, a: f64, b: f64) -> Color {
+        let fy = (l + 16.) / 116.;
+        let (fx, fz) = (fy + a / 500., fy - b / 200.);
+        let (xn, yn, zn) = D65_WHITE;
+        Color::from_xyz(
+            xn * lab_f_inverse(fx),
+            yn * lab_f_inverse(fy),
+            zn * lab_f_inverse(fz),
+        )
+    }
+}
+
+fn srgb_encode(linear: f64) -> f64 {
+    let linear = linear.clamp(0., 1.);
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1. / 2.4) - 0.055
+    }
+}
+
+fn srgb_decode(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn extremes(color: &Color) -> (f64, f64) {
+    (
+        color.r.max(color.g).max(color.b),
+        color.r.min(color.g).min(color.b),
+    )
+}
+
+/// The hue in degrees shared by HSV and HSL.
+fn hue(color: &Color) -> f64 {
+    let (max, min) = extremes(color);
+    let delta = max - min;
+    let hue = if delta == 0. {
+        0.
+    } else if max == color.r {
+        ((color.g - color.b) / delta).rem_euclid(6.)
+    } else if max == color.g {
+        (color.b - color.r) / delta + 2.
+    } else {
+        (color.r - color.g) / delta + 4.
+    };
+    hue * 60.
+}
+
+/// Builds the linear color from a hue, its chroma and the amount `m` every
+/// encoded channel is lifted by.
+fn from_hue(hue: f64, chroma: f64, m: f64) -> Color {
+    let sector = hue.rem_euclid(360.) / 60.;
+    let x = chroma * (1. - (sector % 2. - 1.).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+    Color::from_srgb(r + m, g + m, b + m)
+}
+
+const LAB_DELTA: f64 = 6. / 29.;
+
+fn lab_f(t: f64) -> f64 {
+    if t > LAB_DELTA.powi(3) {
+        t.cbrt()
+    } else {
+        t / (3. * LAB_DELTA * LAB_DELTA) + 4. / 29.
+    }
+}
+
+fn lab_f_inverse(t: f64) -> f64 {
+    if t > LAB_DELTA {
+        t.powi(3)
+    } else {
+        3. * LAB_DELTA * LAB_DELTA * (t - 4. / 29.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn rounded(color: Color, places: u32) -> (f64, f64, f64) {
+        rounded3((color.r, color.g, color.b), places)
+    }
+
+    fn rounded3((a, b, c): (f64, f64, f64), places: u32) -> (f64, f64, f64) {
+        (
+            a.specific_round(places),
+            b.specific_round(places),
+            c.specific_round(places),
+        )
+    }
+
+    #[test]
+    fn test_srgb_round_trip() {
+        let color = Color::from_srgb(0.5, 0.02, 1.);
+
+        assert_eq!(rounded(color, 5), (0.21404, 0.00155, 1.));
+        assert_eq!(rounded(color.to_srgb(), 9), (0.5, 0.02, 1.));
+
+        // Linear near black, clamped outside [0, 1]
+        let srgb = Color::new(0.5, 0.002, 1.5).to_srgb();
+        assert_eq!(rounded(srgb, 5), (0.73536, 0.02584, 1.));
+        assert_eq!(Color::new(-0.2, 0., 0.).to_srgb().r, 0.);
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let color = Color::from_hex("#ff8800").unwrap();
+
+        assert_eq!(rounded(color, 5), (1., 0.24620, 0.));
+        assert_eq!(
+            rounded(Color::from_hex("f80").unwrap(), 5),
+            (1., 0.24620, 0.)
+        );
+        assert_eq!(color.to_hex(), "#ff8800");
+        assert_eq!(Color::new(2., -1., 0.5).to_hex(), "#ff00bc");
+
+        assert!(Color::from_hex("#ff880").is_err());
+        assert!(Color::from_hex("#gg8800").is_err());
+        assert!(Color::from_hex("").is_err());
+    }
+
+    #[test]
+    fn test_hsv() {
+        let orange = Color::from_hex("#ff8000").unwrap();
+
+        assert_eq!(rounded3(orange.to_hsv(), 3), (30.118, 1., 1.));
+        assert_eq!(Color::from_hsv(0., 1., 1.).to_hex(), "#ff0000");
+        assert_eq!(Color::from_hsv(240., 0.5, 0.8).to_hex(), "#6666cc");
+        assert_eq!(Color::from_hsv(-120., 1., 1.).to_hex(), "#0000ff");
+        assert_eq!(Color::black().to_hsv(), (0., 0., 0.));
+    }
+
+    #[test]
+    fn test_hsl() {
+        let teal = Color::from_hex("#339999").unwrap();
+
+        assert_eq!(rounded3(teal.to_hsl(), 3), (180., 0.5, 0.4));
+        assert_eq!(Color::from_hsl(180., 0.5, 0.4).to_hex(), "#339999");
+        assert_eq!(Color::from_hsl(90., 0., 0.6).to_hex(), "#999999");
+    }
+
+    #[test]
+    fn test_xyz() {
+        assert_eq!(rounded3(Color::white().to_xyz(), 4), (0.9505, 1., 1.0888));
+
+        let color = Color::new(0.2, 0.5, 0.8);
+        let (x, y, z) = color.to_xyz();
+        assert_eq!(rounded(Color::from_xyz(x, y, z), 5), (0.2, 0.5, 0.8));
+    }
+
+    #[test]
+    fn test_lab() {
+        assert_eq!(rounded3(Color::white().to_lab(), 3), (100., 0., 0.));
+        assert_eq!(rounded3(Color::black().to_lab(), 3), (0., 0., 0.));
+
+        let red = Color::new(1., 0., 0.);
+        assert_eq!(rounded3(red.to_lab(), 1), (53.2, 80.1, 67.2));
+
+        let color = Color::new(0.01, 0.3, 0.7);
+        let (l, a, b) = color.to_lab();
+        assert_eq!(rounded(Color::from_lab(l, a, b), 5), (0.01, 0.3, 0.7));
+    }
+}
@@ -0,0 +1,142 @@
+use std::{error::Error, fmt, io};
+
+/// Everything that can go wrong in the library, so callers can tell a
+/// singular transform from a malformed scene file or a missing image.
+#[derive(Debug)]
+pub enum StingrayError {
+    /// An operation that needs a square matrix was given a `rows` x `cols` one.
+    NotSquare { rows: usize, cols: usize },
+    /// A matrix or transformation that has no inverse.
+    Singular,
+    /// Operands whose sizes, as (rows, cols), do not fit together.
+    DimensionMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// Material settings outside the range the lighting model allows.
+    InvalidMaterial(String),
+    /// A value the caller passed in that cannot be used, such as zero samples.
+    InvalidArgument(String),
+    /// A valid request the renderer does not implement, such as tiled EXR.
+    Unsupported(String),
+    /// Malformed input: scene files, meshes, images, colour codes.
+    Parse(String),
+    /// Reading or writing `path` failed.
+    Io { path: String, source: io::Error },
+    /// Another error, with where it happened, such as the scene node that
+    /// referenced a broken mesh.
+    Context {
+        context: String,
+        source: Box<StingrayError>,
+    },
+}
+
+impl StingrayError {
+    /// Wraps the error with a note on where it happened.
+    pub fn context(self, context: impl Into<String>) -> StingrayError {
+        StingrayError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The message and those of every error behind it, joined by colons,
+    /// for showing to a user. `Display` gives only the outermost message.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = self.source();
+        while let Some(error) = source {
+            report.push_str(&format!(": {error}"));
+            source = error.source();
+        }
+        report
+    }
+
+    /// The underlying error once any context is peeled off.
+    pub fn root_cause(&self) -> &StingrayError {
+        match self {
+            StingrayError::Context { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for StingrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StingrayError::NotSquare { rows, cols } => {
+                write!(f, "matrix is {rows}x{cols}, expected a square matrix")
+            }
+            StingrayError::Singular => write!(f, "matrix is singular"),
+            StingrayError::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a {}x{} matrix, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            StingrayError::InvalidMaterial(message)
+            | StingrayError::InvalidArgument(message)
+            | StingrayError::Unsupported(message)
+            | StingrayError::Parse(message) => write!(f, "{message}"),
+            // The causes are left to `source`, so reporters that walk the
+            // chain do not print them twice
+            StingrayError::Io { path, .. } => write!(f, "{path}"),
+            StingrayError::Context { context, .. } => write!(f, "{context}"),
+        }
+    }
+}
+
+impl Error for StingrayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StingrayError::Io { source, .. } => Some(source),
+            StingrayError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            StingrayError::NotSquare { rows: 2, cols: 3 }.to_string(),
+            "matrix is 2x3, expected a square matrix"
+        );
+        assert_eq!(
+            StingrayError::DimensionMismatch {
+                expected: (3, 1),
+                found: (2, 1)
+            }
+            .to_string(),
+            "expected a 3x1 matrix, found 2x1"
+        );
+        assert_eq!(
+            StingrayError::Parse("line 3: invalid number 'x'".to_string()).to_string(),
+            "line 3: invalid number 'x'"
+        );
+    }
+
+    #[test]
+    fn test_context_and_source() {
+        let io = io::Error::new(io::ErrorKind::NotFound, "not found");
+        let error = StingrayError::Io {
+            path: "mesh.obj".to_string(),
+            source: io,
+        }
+        .context("scene[2].file");
+
+        assert_eq!(error.to_string(), "scene[2].file");
+        assert_eq!(error.report(), "scene[2].file: mesh.obj: not found");
+        assert!(matches!(error.root_cause(), StingrayError::Io { .. }));
+        let source = error.source().unwrap();
+        assert_eq!(source.to_string(), "mesh.obj");
+        assert_eq!(source.source().unwrap().to_string(), "not found");
+        assert!(matches!(
+            StingrayError::Singular.root_cause(),
+            StingrayError::Singular
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+use super::matrix::Matrix;
+use crate::error::StingrayError;
+
+/// The LU decomposition of a square matrix with partial pivoting, so that
+/// rows `permutation` of the original equal `L * U`. `L` has a unit
+/// diagonal and is stored below the diagonal of `lu`, `U` on and above it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LuDecomposition {
+    lu: Matrix,
+    permutation: Vec<usize>,
+    /// +1 or -1 depending on whether an even or odd number of rows swapped.
+    sign: f64,
+    singular: bool,
+}
+
+impl LuDecomposition {
+    pub fn new(matrix: &Matrix) -> Result<LuDecomposition, StingrayError> {
+        let n = matrix.rows();
+        if n != matrix.cols() {
+            return Err(StingrayError::NotSquare {
+                rows: n,
+                cols: matrix.cols(),
+            });
+        }
+
+        let tolerance = tolerance(matrix);
+        let mut lu = matrix.clone();
+        let mut permutation: Vec<usize> = (0..n).collect();
+        let mut sign = 1.;
+        let mut singular = false;
+
+        for k in 0..n {
+            // Pivot on the largest remaining entry in the column so no
+            // multiplier exceeds one
+            let pivot = (k..n)
+                .max_by(|&a, &b| lu[a][k].abs().total_cmp(&lu[b][k].abs()))
+                .unwrap_or(k);
+            if lu[pivot][k].abs() <= tolerance {
+                singular = true;
+                continue;
+            }
+            if pivot != k {
+                lu.data.swap(pivot, k);
+                permutation.swap(pivot, k);
+                sign = -sign;
+            }
+            for i in k + 1..n {
+                let factor = lu[i][k] / lu[k][k];
+                lu[i][k] = factor;
+                for j in k + 1..n {
+                    lu[i][j] -= factor * lu[k][j];
+                }
+            }
+        }
+
+        Ok(LuDecomposition {
+            lu,
+            permutation,
+            sign,
+            singular,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.permutation.len()
+    }
+
+    /// Whether a pivot vanished, relative to the size of the entries.
+    pub fn is_singular(&self) -> bool {
+        self.singular
+    }
+
+    /// The product of the pivots, or exactly zero when `is_singular`, so a
+    /// determinant of zero and a `Singular` inverse always go together.
+    pub fn determinant(&self) -> f64 {
+        if self.singular {
+            return 0.;
+        }
+        (0..self.size()).fold(self.sign, |det, i| det * self.lu[i][i])
+    }
+
+    /// Solves `A * x = rhs` by forward and back substitution.
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, StingrayError> {
+        let n = self.size();
+        if rhs.len() != n {
+            return Err(StingrayError::DimensionMismatch {
+                expected: (n, 1),
+                found: (rhs.len(), 1),
+            });
+        }
+        if self.singular {
+            return Err(StingrayError::Singular);
+        }
+
+        let mut x: Vec<f64> = self.permutation.iter().map(|&row| rhs[row]).collect();
+        for i in 0..n {
+            for j in 0..i {
+                x[i] -= self.lu[i][j] * x[j];
+            }
+        }
+        for i in (0..n).rev() {
+            for j in i + 1..n {
+                x[i] -= self.lu[i][j] * x[j];
+            }
+            x[i] /= self.lu[i][i];
+        }
+        Ok(x)
+    }
+
+    /// Solves for each column of the identity in turn.
+    pub fn inverse(&self) -> Result<Matrix, StingrayError> {
+        let n = self.size();
+        let mut inverse = Matrix::new(n, n);
+        let mut unit = vec![0.; n];
+        for j in 0..n {
+            unit[j] = 1.;
+            for (i, value) in self.solve(&unit)?.into_iter().enumerate() {
+                inverse[i][j] = value;
+            }
+            unit[j] = 0.;
+        }
+        Ok(inverse)
+    }
+}
+
+/// Pivots at or below this are treated as zero. Scaling by the largest
+/// entry keeps the test independent of the matrix's units.
+pub(crate) fn tolerance(matrix: &Matrix) -> f64 {
+    let largest = matrix
+        .data
+        .iter()
+        .flatten()
+        .fold(0., |largest: f64, value| largest.max(value.abs()));
+    largest * matrix.rows().max(matrix.cols()) as f64 * f64::EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn rounded(values: &[f64], places: u32) -> Vec<f64> {
+        values.iter().map(|v| v.specific_round(places)).collect()
+    }
+
+    #[test]
+    fn test_decomposition_reconstructs_matrix() {
+        let matrix = Matrix::from_vec(vec![vec![1., 2., 3.], vec![-5., 8., -4.], vec![2., 6., 4.]]);
+        let lu = LuDecomposition::new(&matrix).unwrap();
+
+        let mut lower = Matrix::new(3, 3);
+        let mut upper = Matrix::new(3, 3);
+        for i in 0..3 {
+            for j in 0..3 {
+                match i.cmp(&j) {
+                    std::cmp::Ordering::Greater => lower[i][j] = lu.lu[i][j],
+                    std::cmp::Ordering::Equal => {
+                        lower[i][j] = 1.;
+                        upper[i][j] = lu.lu[i][j];
+                    }
+                    std::cmp::Ordering::Less => upper[i][j] = lu.lu[i][j],
+                }
+            }
+        }
+        let product = (lower * upper).round(9);
+
+        // The largest entry in the first column was moved to the top
+        assert_eq!(lu.permutation[0], 1);
+        for (i, &row) in lu.permutation.iter().enumerate() {
+            assert_eq!(product[i], matrix.round(9)[row]);
+        }
+        assert_eq!(lu.determinant().specific_round(9), -58.);
+    }
+
+    #[test]
+    fn test_solve() {
+        let matrix = Matrix::from_vec(vec![
+            vec![2., 1., -1.],
+            vec![-3., -1., 2.],
+            vec![-2., 1., 2.],
+        ]);
+        let lu = LuDecomposition::new(&matrix).unwrap();
+
+        assert_eq!(
+            rounded(&lu.solve(&[8., -11., -3.]).unwrap(), 9),
+            vec![2., 3., -1.]
+        );
+        assert!(matches!(
+            lu.solve(&[1., 2.]),
+            Err(StingrayError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_needs_pivoting() {
+        // A zero in the top left stops elimination without row swaps
+        let matrix = Matrix::from_vec(vec![vec![0., 1.], vec![1., 0.]]);
+        let lu = LuDecomposition::new(&matrix).unwrap();
+
+        assert!(!lu.is_singular());
+        assert_eq!(lu.determinant(), -1.);
+        assert_eq!(lu.solve(&[3., 4.]).unwrap(), vec![4., 3.]);
+    }
+
+    #[test]
+    fn test_singular() {
+        let matrix = Matrix::from_vec(vec![vec![1., 2., 3.], vec![2., 4., 6.], vec![1., 0., 1.]]);
+        let lu = LuDecomposition::new(&matrix).unwrap();
+
+        assert!(lu.is_singular());
+        assert_eq!(lu.determinant(), 0.);
+        assert!(matches!(
+            lu.solve(&[1., 2., 3.]),
+            Err(StingrayError::Singular)
+        ));
+        assert!(lu.inverse().is_err());
+
+        // Rounding leaves a tiny pivot rather than an exact zero
+        let nearly = Matrix::from_vec(vec![vec![1., 2., 3.], vec![4., 5., 6.], vec![7., 8., 9.]]);
+        let lu = LuDecomposition::new(&nearly).unwrap();
+        assert!(lu.is_singular());
+        assert_eq!(lu.determinant(), 0.);
+
+        assert!(matches!(
+            LuDecomposition::new(&Matrix::new(2, 3)),
+            Err(StingrayError::NotSquare { rows: 2, cols: 3 })
+        ));
+    }
+}
@@ -1,6 +1,14 @@
 use std::ops::{Add, Div, Index, IndexMut, Mul};
 
-use super::{point::Point, ray::Ray, vector::Vector};
+use crate::{error::StingrayError, traits::TryMul};
+
+use super::{
+    lu::{self, LuDecomposition},
+    matrix4::Matrix4,
+    point::Point,
+    ray::Ray,
+    vector::Vector,
+};
 
 pub enum Rotation {
     X,
@@ -26,82 +34,29 @@ impl Matrix {
     }
 
     pub fn identity() -> Matrix {
-        Matrix {
-            rows: 4,
-            cols: 4,
-            data: vec![
-                vec![1., 0., 0., 0.],
-                vec![0., 1., 0., 0.],
-                vec![0., 0., 1., 0.],
-                vec![0., 0., 0., 1.],
-            ],
-        }
+        Matrix4::identity().into()
     }
 
     pub fn translation(x: f64, y: f64, z: f64) -> Matrix {
-        Matrix {
-            rows: 4,
-            cols: 4,
-            data: vec![
-                vec![1., 0., 0., x],
-                vec![0., 1., 0., y],
-                vec![0., 0., 1., z],
-                vec![0., 0., 0., 1.],
-            ],
-        }
+        Matrix4::translation(x, y, z).into()
     }
 
     pub fn scaling(x: f64, y: f64, z: f64) -> Matrix {
-        Matrix {
-            rows: 4,
-            cols: 4,
-            data: vec![
-                vec![x, 0., 0., 0.],
-                vec![0., y, 0., 0.],
-                vec![0., 0., z, 0.],
-                vec![0., 0., 0., 1.],
-            ],
-        }
+        Matrix4::scaling(x, y, z).into()
     }
 
     pub fn rotation(angle: f64, axis: Rotation) -> Matrix {
-        let mut matrix = Matrix::identity();
-        let cosr = angle.cos();
-        let sinr = angle.sin();
-        match axis {
-            Rotation::X => {
-                matrix[1][1] = cosr;
-                matrix[2][2] = cosr;
-                matrix[1][2] = -sinr;
-                matrix[2][1] = sinr;
-            }
-            Rotation::Y => {
-                matrix[0][0] = cosr;
-                matrix[2][2] = cosr;
-                matrix[0][2] = sinr;
-                matrix[2][0] = -sinr;
-            }
-            Rotation::Z => {
-                matrix[0][0] = cosr;
-                matrix[1][1] = cosr;
-                matrix[0][1] = -sinr;
-                matrix[1][0] = sinr;
-            }
-        }
-        matrix
+        Matrix4::rotation(angle, axis).into()
     }
 
     pub fn shearing(x_y: f64, x_z: f64, y_x: f64, y_z: f64, z_x: f64, z_y: f64) -> Matrix {
-        Matrix {
-            rows: 4,
-            cols: 4,
-            data: vec![
-                vec![1., x_y, x_z, 0.],
-                vec![y_x, 1., y_z, 0.],
-                vec![z_x, z_y, 1., 0.],
-                vec![0., 0., 0., 1.],
-            ],
-        }
+        Matrix4::shearing(x_y, x_z, y_x, y_z, z_x, z_y).into()
+    }
+
+    /// Orients the world relative to an eye at `from` looking towards `to`,
+    /// with `up` roughly pointing upwards.
+    pub fn view_transform(from: Point, to: Point, up: Vector) -> Matrix {
+        Matrix4::view_transform(from, to, up).into()
     }
 
     pub fn from_vec(data: Vec<Vec<f64>>) -> Matrix {
@@ -110,13 +65,24 @@ impl Matrix {
         Matrix { rows, cols, data }
     }
 
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
     pub fn to_vec(&self) -> Vec<Vec<f64>> {
         self.data.clone()
     }
 
-    pub fn transpose(&self) -> Result<Matrix, String> {
+    pub fn transpose(&self) -> Result<Matrix, StingrayError> {
         if self.rows != self.cols {
-            return Err("Matrix is not square".to_string());
+            return Err(StingrayError::NotSquare {
+                rows: self.rows,
+                cols: self.cols,
+            });
         }
         let mut result = Matrix::new(self.cols, self.rows);
         for i in 0..self.rows {
@@ -127,66 +93,84 @@ impl Matrix {
         Ok(result)
     }
 
-    pub fn determinant(&self) -> Result<f64, String> {
-        if self.rows != self.cols {
-            return Err("Matrix is not square".to_string());
-        }
-
-        Self::sub_determinant(self.to_vec())
+    /// Factors the matrix into lower and upper triangular parts, which
+    /// the determinant, inverse and solver share.
+    pub fn lu(&self) -> Result<LuDecomposition, StingrayError> {
+        LuDecomposition::new(self)
     }
 
-    fn sub_determinant(matrix: Vec<Vec<f64>>) -> Result<f64, String> {
-        if matrix.len() == 1 {
-            return Ok(matrix[0][0]);
-        }
-        if matrix.len() == 2 {
-            return Ok(matrix[0][0] * matrix[1][1] - matrix[0][1] * matrix[1][0]);
-        }
-        let mut result = 0.;
-        for i in 0..matrix.len() {
-            // this should slice the row and column
-            let sub_matrix = Self::sub_matrix(&matrix, 0, i);
-            let polarity = if i % 2 == 0 { 1. } else { -1. };
-            result += matrix.first().expect("No last row")[i]
-                * polarity
-                * Self::sub_determinant(sub_matrix)?;
-        }
+    /// Zero for any matrix `inverse` rejects as singular, including those
+    /// that are singular only up to rounding.
+    pub fn determinant(&self) -> Result<f64, StingrayError> {
+        Ok(self.lu()?.determinant())
+    }
 
-        Ok(result)
+    pub fn inverse(&self) -> Result<Matrix, StingrayError> {
+        self.lu()?.inverse()
     }
 
-    fn sub_matrix(matrix: &Vec<Vec<f64>>, row: usize, col: usize) -> Vec<Vec<f64>> {
-        matrix
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| *i != row)
-            .map(|(_, row)| {
-                row.iter()
-                    .enumerate()
-                    .filter(|(j, _)| *j != col)
-                    .map(|(_, &val)| val)
-                    .collect()
-            })
-            .collect()
+    /// Solves `self * x = rhs` for `x`.
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, StingrayError> {
+        self.lu()?.solve(rhs)
     }
 
-    fn cofactor(&self) -> Result<Matrix, String> {
-        let mut result = Matrix::new(self.rows, self.cols);
-        let mut polarities = String::new();
-        for i in 0..self.rows {
-            for j in 0..self.cols {
-                let polarity = if (i + j) % 2 == 0 { 1. } else { -1. };
-                result[i][j] =
-                    polarity * Self::sub_determinant(Self::sub_matrix(&self.to_vec(), i, j))?;
+    /// The number of linearly independent rows, found by Gaussian
+    /// elimination. Works for any shape of matrix.
+    pub fn rank(&self) -> usize {
+        let tolerance = lu::tolerance(self);
+        let mut rows = self.data.clone();
+        let mut rank = 0;
+        for col in 0..self.cols {
+            if rank == rows.len() {
+                break;
+            }
+            let pivot = (rank..rows.len())
+                .max_by(|&a, &b| rows[a][col].abs().total_cmp(&rows[b][col].abs()))
+                .unwrap();
+            if rows[pivot][col].abs() <= tolerance {
+                continue;
+            }
+            rows.swap(pivot, rank);
+            let (done, rest) = rows.split_at_mut(rank + 1);
+            let pivot_row = &done[rank];
+            for row in rest {
+                let factor = row[col] / pivot_row[col];
+                for (value, pivot) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *value -= factor * pivot;
+                }
             }
+            rank += 1;
         }
-        Ok(result)
+        rank
     }
 
-    pub fn inverse(&self) -> Result<Matrix, String> {
-        let cofactor = self.cofactor()?.transpose()?;
-        let det = self.determinant()?;
-        Ok(cofactor / det)
+    /// The condition number in the 1-norm, `‖A‖ ‖A⁻¹‖`. Solutions lose
+    /// roughly log10 of it in significant digits; singular matrices give
+    /// infinity.
+    pub fn condition_number(&self) -> Result<f64, StingrayError> {
+        let lu = self.lu()?;
+        if lu.is_singular() {
+            return Ok(f64::INFINITY);
+        }
+        Ok(self.norm_1() * lu.inverse()?.norm_1())
+    }
+
+    /// Points and vectors can only be transformed by 4x4 matrices.
+    fn expect_transform(&self) -> Result<(), StingrayError> {
+        if (self.rows, self.cols) != (4, 4) {
+            return Err(StingrayError::DimensionMismatch {
+                expected: (4, 4),
+                found: (self.rows, self.cols),
+            });
+        }
+        Ok(())
+    }
+
+    /// The largest absolute column sum.
+    fn norm_1(&self) -> f64 {
+        (0..self.cols)
+            .map(|j| self.data.iter().map(|row| row[j].abs()).sum::<f64>())
+            .fold(0., f64::max)
     }
 
     pub fn round(&self, digits: usize) -> Matrix {
@@ -201,7 +185,7 @@ impl Matrix {
                 .data
                 .clone()
                 .into_iter()
-                .map(|row| row.into_iter().map(|val| round_to(val, 5)).collect())
+                .map(|row| row.into_iter().map(|val| round_to(val, digits)).collect())
                 .collect(),
         }
     }
@@ -224,8 +208,23 @@ impl IndexMut<usize> for Matrix {
 impl Mul for Matrix {
     type Output = Matrix;
 
+    /// Panics if `rhs` does not have as many rows as `self` has columns;
+    /// use [`TryMul`] to handle that instead.
     fn mul(self, rhs: Matrix) -> Matrix {
-        assert_eq!(self.cols, rhs.rows);
+        self.try_mul(&rhs).unwrap_or_else(|error| panic!("{error}"))
+    }
+}
+
+impl TryMul<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn try_mul(self, rhs: &Matrix) -> Result<Matrix, StingrayError> {
+        if self.cols != rhs.rows {
+            return Err(StingrayError::DimensionMismatch {
+                expected: (self.cols, rhs.cols),
+                found: (rhs.rows, rhs.cols),
+            });
+        }
         let mut result = Matrix::new(self.rows, rhs.cols);
         for i in 0..self.rows {
             for j in 0..rhs.cols {
@@ -236,7 +235,7 @@ impl Mul for Matrix {
                 result[i][j] = sum;
             }
         }
-        result
+        Ok(result)
     }
 }
 
@@ -278,6 +277,24 @@ impl Mul<Vector> for Matrix {
     }
 }
 
+impl TryMul<Point> for &Matrix {
+    type Output = Point;
+
+    fn try_mul(self, point: Point) -> Result<Point, StingrayError> {
+        self.expect_transform()?;
+        Ok(self.clone() * point)
+    }
+}
+
+impl TryMul<Vector> for &Matrix {
+    type Output = Vector;
+
+    fn try_mul(self, vector: Vector) -> Result<Vector, StingrayError> {
+        self.expect_transform()?;
+        Ok(self.clone() * vector)
+    }
+}
+
 impl Mul<Ray> for Matrix {
     type Output = Ray;
     fn mul(self, ray: Ray) -> Ray {
@@ -306,6 +323,7 @@ impl Add<Point> for Matrix {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::traits::SpecificRound;
 
     #[test]
     fn test_matrix_2x2() {
@@ -420,7 +438,7 @@ mod tests {
     fn test_determinant_3x3() {
         let matrix = Matrix::from_vec(vec![vec![1., 2., 3.], vec![-5., 8., -4.], vec![2., 6., 4.]]);
         let result = matrix.determinant().expect("No determinant");
-        assert_eq!(result, -58.);
+        assert_eq!(result.specific_round(9), -58.);
     }
 
     #[test]
@@ -433,7 +451,7 @@ mod tests {
         ]);
         let result = matrix.determinant().expect("No determinant");
 
-        assert_eq!(result, 532.);
+        assert_eq!(result.specific_round(9), 532.);
     }
 
     #[test]
@@ -457,6 +475,54 @@ mod tests {
         assert_eq!(result, answer);
     }
 
+    #[test]
+    fn test_view_transform_default() {
+        let from = Point::new(0., 0., 0.);
+        let to = Point::new(0., 0., -1.);
+        let up = Vector::new(0., 1., 0.);
+
+        assert_eq!(Matrix::view_transform(from, to, up), Matrix::identity());
+    }
+
+    #[test]
+    fn test_view_transform_positive_z() {
+        let from = Point::new(0., 0., 0.);
+        let to = Point::new(0., 0., 1.);
+        let up = Vector::new(0., 1., 0.);
+
+        assert_eq!(
+            Matrix::view_transform(from, to, up),
+            Matrix::scaling(-1., 1., -1.)
+        );
+    }
+
+    #[test]
+    fn test_view_transform_moves_world() {
+        let from = Point::new(0., 0., 8.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 1., 0.);
+
+        assert_eq!(
+            Matrix::view_transform(from, to, up),
+            Matrix::translation(0., 0., -8.)
+        );
+    }
+
+    #[test]
+    fn test_view_transform_arbitrary() {
+        let from = Point::new(1., 3., 2.);
+        let to = Point::new(4., -2., 8.);
+        let up = Vector::new(1., 1., 0.);
+
+        let answer = Matrix::from_vec(vec![
+            vec![-0.50709, 0.50709, 0.67612, -2.36643],
+            vec![0.76772, 0.60609, 0.12122, -2.82843],
+            vec![-0.35857, 0.59761, -0.71714, 0.],
+            vec![0., 0., 0., 1.],
+        ]);
+        assert_eq!(Matrix::view_transform(from, to, up).round(5), answer);
+    }
+
     #[test]
     fn test_matrix_translate() {
         let matrix = Matrix::translation(1., 2., 3.);
@@ -465,4 +531,117 @@ mod tests {
         let vector = Vector::new(1., 2., 3.);
         assert_eq!(matrix.clone() * vector, vector);
     }
+
+    #[test]
+    fn test_inverse_singular() {
+        let matrix = Matrix::from_vec(vec![
+            vec![-4., 2., -2., -3.],
+            vec![9., 6., 2., 6.],
+            vec![0., -5., 1., -5.],
+            vec![0., 0., 0., 0.],
+        ]);
+
+        assert_eq!(matrix.determinant().unwrap(), 0.);
+        assert!(matches!(matrix.inverse(), Err(StingrayError::Singular)));
+
+        // Singular only up to rounding, which the determinant reports as zero too
+        let nearly = Matrix::from_vec(vec![vec![1., 2., 3.], vec![4., 5., 6.], vec![7., 8., 9.]]);
+        assert_eq!(nearly.determinant().unwrap(), 0.);
+        assert!(matches!(nearly.inverse(), Err(StingrayError::Singular)));
+    }
+
+    #[test]
+    fn test_solve() {
+        let matrix = Matrix::from_vec(vec![
+            vec![-5., 2., 6., -8.],
+            vec![1., -5., 1., 8.],
+            vec![7., 7., -6., -7.],
+            vec![1., -3., 7., 4.],
+        ]);
+        let x = [1., -2., 0.5, 3.];
+        let rhs: Vec<f64> = matrix
+            .data
+            .iter()
+            .map(|row| row.iter().zip(x).map(|(a, b)| a * b).sum())
+            .collect();
+        let solution = matrix.solve(&rhs).unwrap();
+
+        for (found, expected) in solution.iter().zip(x) {
+            assert_eq!(found.specific_round(9), expected);
+        }
+        assert!(Matrix::new(2, 3).solve(&[1., 2.]).is_err());
+    }
+
+    #[test]
+    fn test_large_determinant() {
+        // Far beyond what cofactor expansion could finish
+        let n = 20;
+        let mut matrix = Matrix::new(n, n);
+        for i in 0..n {
+            matrix[i][i] = 2.;
+            if i + 1 < n {
+                matrix[i][i + 1] = -1.;
+                matrix[i + 1][i] = -1.;
+            }
+        }
+
+        // The tridiagonal (2, -1) matrix has determinant n + 1
+        assert_eq!(matrix.determinant().unwrap().specific_round(6), 21.);
+    }
+
+    #[test]
+    fn test_rank() {
+        let matrix = Matrix::from_vec(vec![
+            vec![1., 2., 3., 4.],
+            vec![2., 4., 6., 8.],
+            vec![0., 1., 1., 0.],
+        ]);
+
+        assert_eq!(matrix.rank(), 2);
+        assert_eq!(Matrix::identity().rank(), 4);
+        assert_eq!(Matrix::new(3, 2).rank(), 0);
+    }
+
+    #[test]
+    fn test_condition_number() {
+        assert_eq!(Matrix::identity().condition_number().unwrap(), 1.);
+        assert_eq!(
+            Matrix::scaling(1., 10., 1.).condition_number().unwrap(),
+            10.
+        );
+
+        let nearly_singular = Matrix::from_vec(vec![vec![1., 1.], vec![1., 1.0001]]);
+        assert!(nearly_singular.condition_number().unwrap() > 1e4);
+        let singular = Matrix::from_vec(vec![vec![1., 2.], vec![2., 4.]]);
+        assert_eq!(singular.condition_number().unwrap(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_try_mul() {
+        let a = Matrix::from_vec(vec![vec![1., 2., 3.], vec![4., 5., 6.]]);
+        let b = Matrix::from_vec(vec![vec![1.], vec![0.], vec![-1.]]);
+
+        assert_eq!(a.try_mul(&b).unwrap().to_vec(), vec![vec![-2.], vec![-2.]]);
+        assert!(matches!(
+            b.try_mul(&b),
+            Err(StingrayError::DimensionMismatch {
+                expected: (1, 1),
+                found: (3, 1)
+            })
+        ));
+        assert_eq!(
+            Matrix::translation(1., 2., 3.)
+                .try_mul(Point::new(0., 0., 0.))
+                .unwrap(),
+            Point::new(1., 2., 3.)
+        );
+        assert!(a.try_mul(Vector::new(1., 0., 0.)).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a 2x1 matrix, found 3x1")]
+    fn test_mul_mismatch_panics() {
+        let a = Matrix::new(2, 2);
+        let _ = a * Matrix::new(3, 1);
+    }
 }
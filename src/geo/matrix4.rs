@@ -0,0 +1,435 @@
+use std::ops::{Index, IndexMut, Mul};
+
+use crate::error::StingrayError;
+
+use super::{
+    matrix::{Matrix, Rotation},
+    point::Point,
+    ray::Ray,
+    vector::Vector,
+};
+
+/// A 4x4 matrix for transformations, stored inline so it can be copied
+/// freely. The general `Matrix` remains for other sizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4(pub [[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub fn new(data: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4(data)
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Matrix4 {
+        Matrix4([
+            [1., 0., 0., x],
+            [0., 1., 0., y],
+            [0., 0., 1., z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scaling(x: f64, y: f64, z: f64) -> Matrix4 {
+        Matrix4([
+            [x, 0., 0., 0.],
+            [0., y, 0., 0.],
+            [0., 0., z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn rotation(angle: f64, axis: Rotation) -> Matrix4 {
+        let mut matrix = Matrix4::identity();
+        let cosr = angle.cos();
+        let sinr = angle.sin();
+        match axis {
+            Rotation::X => {
+                matrix[1][1] = cosr;
+                matrix[2][2] = cosr;
+                matrix[1][2] = -sinr;
+                matrix[2][1] = sinr;
+            }
+            Rotation::Y => {
+                matrix[0][0] = cosr;
+                matrix[2][2] = cosr;
+                matrix[0][2] = sinr;
+                matrix[2][0] = -sinr;
+            }
+            Rotation::Z => {
+                matrix[0][0] = cosr;
+                matrix[1][1] = cosr;
+                matrix[0][1] = -sinr;
+                matrix[1][0] = sinr;
+            }
+        }
+        matrix
+    }
+
+    /// Rotates by `angle` radians about `axis`, which need not be
+    /// normalized. Matches `rotation` for the principal axes.
+    pub fn rotation_about(axis: Vector, angle: f64) -> Matrix4 {
+        let Vector { x, y, z } = axis.norm();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1. - cos;
+        Matrix4([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn shearing(x_y: f64, x_z: f64, y_x: f64, y_z: f64, z_x: f64, z_y: f64) -> Matrix4 {
+        Matrix4([
+            [1., x_y, x_z, 0.],
+            [y_x, 1., y_z, 0.],
+            [z_x, z_y, 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Orients the world relative to an eye at `from` looking towards `to`,
+    /// with `up` roughly pointing upwards.
+    pub fn view_transform(from: Point, to: Point, up: Vector) -> Matrix4 {
+        let forward = (to - from).norm();
+        let left = forward.cross(up.norm());
+        let true_up = left.cross(forward);
+        let orientation = Matrix4([
+            [left.x, left.y, left.z, 0.],
+            [true_up.x, true_up.y, true_up.z, 0.],
+            [-forward.x, -forward.y, -forward.z, 0.],
+            [0., 0., 0., 1.],
+        ]);
+        orientation * Matrix4::translation(-from.x, -from.y, -from.z)
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let m = &self.0;
+        Matrix4(std::array::from_fn(|i| std::array::from_fn(|j| m[j][i])))
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let (s, c) = self.minors();
+        determinant_from_minors(&s, &c)
+    }
+
+    /// The closed-form inverse, built from the same 2x2 minors as the
+    /// determinant.
+    pub fn inverse(&self) -> Result<Matrix4, StingrayError> {
+        let m = &self.0;
+        let (s, c) = self.minors();
+        let det = determinant_from_minors(&s, &c);
+        if det == 0. || !det.is_finite() {
+            return Err(StingrayError::Singular);
+        }
+        let inv = 1. / det;
+
+        Ok(Matrix4([
+            [
+                (m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3]) * inv,
+                (-m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3]) * inv,
+                (m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3]) * inv,
+                (-m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3]) * inv,
+            ],
+            [
+                (-m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1]) * inv,
+                (m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1]) * inv,
+                (-m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1]) * inv,
+                (m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1]) * inv,
+            ],
+            [
+                (m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0]) * inv,
+                (-m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0]) * inv,
+                (m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0]) * inv,
+                (-m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0]) * inv,
+            ],
+            [
+                (-m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0]) * inv,
+                (m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0]) * inv,
+                (-m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0]) * inv,
+                (m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0]) * inv,
+            ],
+        ]))
+    }
+
+    /// The 2x2 minors of the top two rows (`s`) and bottom two rows (`c`),
+    /// taken over each pair of columns.
+    fn minors(&self) -> ([f64; 6], [f64; 6]) {
+        let m = &self.0;
+        let pair = |r: usize, a: usize, b: usize| m[r][a] * m[r + 1][b] - m[r + 1][a] * m[r][b];
+        let pairs = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        (
+            pairs.map(|(a, b)| pair(0, a, b)),
+            pairs.map(|(a, b)| pair(2, a, b)),
+        )
+    }
+
+    pub fn round(&self, digits: usize) -> Matrix4 {
+        let factor = 10.0_f64.powi(digits as i32);
+        Matrix4(
+            self.0
+                .map(|row| row.map(|val| (val * factor).round() / factor)),
+        )
+    }
+}
+
+/// Laplace expansion of the determinant over the top and bottom row pairs.
+fn determinant_from_minors(s: &[f64; 6], c: &[f64; 6]) -> f64 {
+    s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Matrix4::identity()
+    }
+}
+
+impl Index<usize> for Matrix4 {
+    type Output = [f64; 4];
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Matrix4 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        Matrix4(std::array::from_fn(|i| {
+            std::array::from_fn(|j| (0..4).map(|k| self[i][k] * rhs[k][j]).sum())
+        }))
+    }
+}
+
+impl Mul<Point> for Matrix4 {
+    type Output = Point;
+
+    fn mul(self, other: Point) -> Point {
+        let x = self[0][0] * other.x + self[0][1] * other.y + self[0][2] * other.z + self[0][3];
+        let y = self[1][0] * other.x + self[1][1] * other.y + self[1][2] * other.z + self[1][3];
+        let z = self[2][0] * other.x + self[2][1] * other.y + self[2][2] * other.z + self[2][3];
+        Point { x, y, z }
+    }
+}
+
+impl Mul<Vector> for Matrix4 {
+    type Output = Vector;
+
+    fn mul(self, other: Vector) -> Vector {
+        let x = self[0][0] * other.x + self[0][1] * other.y + self[0][2] * other.z;
+        let y = self[1][0] * other.x + self[1][1] * other.y + self[1][2] * other.z;
+        let z = self[2][0] * other.x + self[2][1] * other.y + self[2][2] * other.z;
+        Vector { x, y, z }
+    }
+}
+
+impl Mul<Ray> for Matrix4 {
+    type Output = Ray;
+
+    fn mul(self, ray: Ray) -> Ray {
+        Ray::new(self * ray.origin, self * ray.direction)
+    }
+}
+
+impl Mul<&Ray> for Matrix4 {
+    type Output = Ray;
+
+    fn mul(self, ray: &Ray) -> Ray {
+        Ray::new(self * ray.origin, self * ray.direction)
+    }
+}
+
+impl From<Matrix4> for Matrix {
+    fn from(matrix: Matrix4) -> Matrix {
+        Matrix::from_vec(matrix.0.iter().map(|row| row.to_vec()).collect())
+    }
+}
+
+impl TryFrom<&Matrix> for Matrix4 {
+    type Error = StingrayError;
+
+    fn try_from(matrix: &Matrix) -> Result<Matrix4, StingrayError> {
+        if (matrix.rows(), matrix.cols()) != (4, 4) {
+            return Err(StingrayError::DimensionMismatch {
+                expected: (4, 4),
+                found: (matrix.rows(), matrix.cols()),
+            });
+        }
+        Ok(Matrix4(std::array::from_fn(|i| {
+            std::array::from_fn(|j| matrix[i][j])
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn example() -> Matrix4 {
+        Matrix4([
+            [-5., 2., 6., -8.],
+            [1., -5., 1., 8.],
+            [7., 7., -6., -7.],
+            [1., -3., 7., 4.],
+        ])
+    }
+
+    #[test]
+    fn test_determinant() {
+        let matrix = Matrix4([
+            [-2., -8., 3., 5.],
+            [-3., 1., 7., 3.],
+            [1., 2., -9., 6.],
+            [-6., 7., 7., -9.],
+        ]);
+
+        assert_eq!(matrix.determinant(), -4071.);
+        assert_eq!(example().determinant(), 532.);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let expected = Matrix4([
+            [0.21805, 0.45113, 0.24060, -0.04511],
+            [-0.80827, -1.45677, -0.44361, 0.52068],
+            [-0.07895, -0.22368, -0.05263, 0.19737],
+            [-0.52256, -0.81391, -0.30075, 0.30639],
+        ]);
+
+        assert_eq!(example().inverse().unwrap().round(5), expected);
+    }
+
+    #[test]
+    fn test_inverse_matches_general_matrix() {
+        let matrix = Matrix4::translation(1., -2., 3.)
+            * Matrix4::rotation(0.3, Rotation::X)
+            * Matrix4::shearing(0.5, 0., 0.2, 0., 0., 1.)
+            * Matrix4::scaling(2., 0.5, 4.);
+        let general = Matrix::from(matrix).inverse().unwrap().round(9);
+
+        assert_eq!(Matrix::from(matrix.inverse().unwrap().round(9)), general);
+    }
+
+    #[test]
+    fn test_product_with_inverse() {
+        let a = example();
+        let b = Matrix4([
+            [8., 2., 2., 2.],
+            [3., -1., 7., 0.],
+            [7., 0., 5., 4.],
+            [6., -2., 0., 5.],
+        ]);
+        let product = a * b;
+
+        assert_eq!((product * b.inverse().unwrap()).round(9), a);
+        assert_eq!((a * a.inverse().unwrap()).round(9), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_singular_matrix() {
+        assert!(Matrix4::scaling(1., 0., 1.).inverse().is_err());
+        assert_eq!(Matrix4::scaling(1., 0., 1.).determinant(), 0.);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let matrix = Matrix4::translation(1., 2., 3.);
+
+        assert_eq!(matrix.transpose()[3], [1., 2., 3., 1.]);
+        assert_eq!(matrix.transpose().transpose(), matrix);
+        assert_eq!(Matrix4::identity().transpose(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_transforms() {
+        let point = Point::new(-3., 4., 5.);
+
+        assert_eq!(
+            Matrix4::translation(5., -3., 2.) * point,
+            Point::new(2., 1., 7.)
+        );
+        assert_eq!(
+            Matrix4::scaling(2., 3., 4.) * Vector::new(-4., 6., 8.),
+            Vector::new(-8., 18., 32.)
+        );
+        // Translation does not move vectors
+        assert_eq!(
+            Matrix4::translation(5., -3., 2.) * Vector::new(-3., 4., 5.),
+            Vector::new(-3., 4., 5.)
+        );
+
+        let rotated = Matrix4::rotation(PI / 2., Rotation::Z) * Point::new(0., 1., 0.);
+        assert_eq!(rotated.x.round(), -1.);
+        assert!(rotated.y.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rotation_about_axis() {
+        for (axis, rotation) in [
+            (Vector::new(1., 0., 0.), Rotation::X),
+            (Vector::new(0., 2., 0.), Rotation::Y),
+            (Vector::new(0., 0., 1.), Rotation::Z),
+        ] {
+            assert_eq!(
+                Matrix4::rotation_about(axis, 0.3).round(12),
+                Matrix4::rotation(0.3, rotation).round(12)
+            );
+        }
+
+        // A third of a turn about the diagonal cycles the axes
+        let turn = Matrix4::rotation_about(Vector::new(1., 1., 1.), 2. * PI / 3.);
+        let point = turn * Point::new(1., 0., 0.);
+        assert_eq!(
+            (point.x.round(), point.y.round(), point.z.round()),
+            (0., 1., 0.)
+        );
+        assert!(point.x.abs() < 1e-12 && point.z.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_matrix_conversions() {
+        let matrix = Matrix4::shearing(1., 2., 3., 4., 5., 6.);
+        let general = Matrix::from(matrix);
+
+        assert_eq!(Matrix4::try_from(&general).unwrap(), matrix);
+        assert!(matches!(
+            Matrix4::try_from(&Matrix::new(3, 3)),
+            Err(StingrayError::DimensionMismatch {
+                expected: (4, 4),
+                found: (3, 3)
+            })
+        ));
+    }
+}
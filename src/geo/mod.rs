@@ -1,6 +1,13 @@
+pub mod lu;
 pub mod matrix;
+pub mod matrix4;
 pub mod objects;
 pub mod point;
+pub mod quaternion;
 pub mod ray;
 mod test;
+pub mod transform;
 pub mod vector;
+
+/// Tolerance used to nudge points off surfaces and to compare nearly equal distances.
+pub const EPSILON: f64 = 1e-5;
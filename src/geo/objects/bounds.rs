@@ -0,0 +1,334 @@
+use crate::geo::{matrix4::Matrix4, point::Point, ray::Ray, EPSILON};
+
+/// An axis-aligned bounding box. Infinite extents are allowed for shapes such
+/// as planes; an empty box has `min` above `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        BoundingBox { min, max }
+    }
+
+    pub fn empty() -> Self {
+        BoundingBox::new(
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        )
+    }
+
+    pub fn infinite() -> Self {
+        BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [
+            self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z,
+        ]
+        .iter()
+        .all(|v| v.is_finite())
+    }
+
+    pub fn add_point(&mut self, point: Point) {
+        self.min = Point::new(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = Point::new(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    pub fn merge(&mut self, other: &BoundingBox) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn centroid(&self) -> Point {
+        Point::new(
+            (self.min.x + self.max.x) / 2.,
+            (self.min.y + self.max.y) / 2.,
+            (self.min.z + self.max.z) / 2.,
+        )
+    }
+
+    /// The box enclosing this one once `transform` is applied to it.
+    pub fn transform(&self, transform: Matrix4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            // Transforming infinite corners produces NaNs, and a rotated
+            // infinite box is unbounded on every axis anyway
+            return BoundingBox::infinite();
+        }
+
+        let mut result = BoundingBox::empty();
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    result.add_point(transform * Point::new(x, y, z));
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether `ray` passes through the box anywhere along its length.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let axes = [
+            (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+            (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+            (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+        ];
+
+        let mut tmin = f64::NEG_INFINITY;
+        let mut tmax = f64::INFINITY;
+        for (origin, direction, min, max) in axes {
+            if direction.abs() < EPSILON {
+                // Parallel to this slab: inside it everywhere or nowhere
+                if origin < min || origin > max {
+                    return false;
+                }
+                continue;
+            }
+            let t0 = (min - origin) / direction;
+            let t1 = (max - origin) / direction;
+            tmin = tmin.max(t0.min(t1));
+            tmax = tmax.min(t0.max(t1));
+        }
+
+        tmin <= tmax
+    }
+}
+
+/// Splits `items` in two at the median of their centroids along the axis
+/// where the centroids spread out the most, the usual way to build one
+/// level of a bounding volume hierarchy.
+pub fn split_at_median<T>(items: Vec<T>, bounds: impl Fn(&T) -> BoundingBox) -> (Vec<T>, Vec<T>) {
+    let centroids: Vec<Point> = items.iter().map(|item| bounds(item).centroid()).collect();
+    let mut spread = BoundingBox::empty();
+    for &centroid in centroids.iter() {
+        spread.add_point(centroid);
+    }
+    let extent = spread.max - spread.min;
+    let axis = |point: Point| {
+        if extent.x >= extent.y && extent.x >= extent.z {
+            point.x
+        } else if extent.y >= extent.z {
+            point.y
+        } else {
+            point.z
+        }
+    };
+
+    let mut keyed: Vec<(f64, T)> = centroids.into_iter().map(axis).zip(items).collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut left: Vec<T> = keyed.into_iter().map(|(_, item)| item).collect();
+    let right = left.split_off(left.len() / 2);
+    (left, right)
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        BoundingBox::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{PI, SQRT_2};
+
+    use super::*;
+    use crate::{
+        geo::{
+            matrix::Rotation,
+            objects::{
+                cone::Cone, cube::Cube, cylinder::Cylinder, geometry::Geometry, plane::Plane,
+                sphere::Sphere, triangle::Triangle,
+            },
+            vector::Vector,
+        },
+        traits::SpecificRound,
+    };
+
+    fn unit_box() -> BoundingBox {
+        BoundingBox::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.))
+    }
+
+    #[test]
+    fn test_add_points() {
+        let mut bounds = BoundingBox::empty();
+        assert!(bounds.is_empty());
+
+        bounds.add_point(Point::new(-5., 2., 0.));
+        bounds.add_point(Point::new(7., 0., -3.));
+
+        assert_eq!(bounds.min, Point::new(-5., 0., -3.));
+        assert_eq!(bounds.max, Point::new(7., 2., 0.));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut bounds = BoundingBox::new(Point::new(-5., -2., 0.), Point::new(7., 4., 4.));
+        bounds.merge(&BoundingBox::new(
+            Point::new(8., -7., -2.),
+            Point::new(14., 2., 8.),
+        ));
+        bounds.merge(&BoundingBox::empty());
+
+        assert_eq!(bounds.min, Point::new(-5., -7., -2.));
+        assert_eq!(bounds.max, Point::new(14., 4., 8.));
+    }
+
+    #[test]
+    fn test_contains() {
+        let bounds = BoundingBox::new(Point::new(5., -2., 0.), Point::new(11., 4., 7.));
+
+        assert!(bounds.contains_point(Point::new(5., -2., 0.)));
+        assert!(bounds.contains_point(Point::new(8., 1., 3.)));
+        assert!(!bounds.contains_point(Point::new(3., 0., 3.)));
+        assert!(!bounds.contains_point(Point::new(8., 1., 8.)));
+        assert!(bounds.contains_box(&BoundingBox::new(
+            Point::new(6., -1., 1.),
+            Point::new(10., 3., 6.)
+        )));
+        assert!(!bounds.contains_box(&BoundingBox::new(
+            Point::new(4., -3., -1.),
+            Point::new(10., 3., 6.)
+        )));
+    }
+
+    #[test]
+    fn test_transform() {
+        let bounds = unit_box();
+        let transform =
+            Matrix4::rotation(PI / 4., Rotation::X) * Matrix4::rotation(PI / 4., Rotation::Y);
+        let result = bounds.transform(transform);
+
+        assert_eq!(result.min.x.specific_round(4), -SQRT_2.specific_round(4));
+        assert_eq!(result.min.y.specific_round(4), -1.7071);
+        assert_eq!(result.min.z.specific_round(4), -1.7071);
+        assert_eq!(result.max.x.specific_round(4), SQRT_2.specific_round(4));
+        assert_eq!(result.max.y.specific_round(4), 1.7071);
+        assert_eq!(result.max.z.specific_round(4), 1.7071);
+    }
+
+    #[test]
+    fn test_transform_infinite() {
+        let bounds = Plane::new().bounds();
+
+        assert_eq!(
+            bounds.transform(Matrix4::translation(0., 1., 0.)),
+            BoundingBox::infinite()
+        );
+    }
+
+    #[test]
+    fn test_intersects() {
+        let bounds = BoundingBox::new(Point::new(5., -2., 0.), Point::new(11., 4., 7.));
+        let cases = [
+            (Point::new(15., 1., 2.), Vector::new(-1., 0., 0.), true),
+            (Point::new(-5., -1., 4.), Vector::new(1., 0., 0.), true),
+            (Point::new(7., 6., 5.), Vector::new(0., -1., 0.), true),
+            (Point::new(9., -5., 6.), Vector::new(0., 1., 0.), true),
+            (Point::new(8., 2., 12.), Vector::new(0., 0., -1.), true),
+            (Point::new(6., 0., -5.), Vector::new(0., 0., 1.), true),
+            (Point::new(8., 1., 3.5), Vector::new(0., 0., 1.), true),
+            (Point::new(9., -1., -8.), Vector::new(2., 4., 6.), false),
+            (Point::new(8., 3., -4.), Vector::new(6., 2., 4.), false),
+            (Point::new(9., -1., -2.), Vector::new(4., 6., 2.), false),
+            (Point::new(4., 0., 9.), Vector::new(0., 0., -1.), false),
+            (Point::new(8., 6., -1.), Vector::new(0., -1., 0.), false),
+            (Point::new(12., 5., 4.), Vector::new(-1., 0., 0.), false),
+        ];
+
+        for (origin, direction, expected) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            assert_eq!(bounds.intersects(&ray), expected, "{origin:?}");
+        }
+    }
+
+    #[test]
+    fn test_shape_bounds() {
+        assert_eq!(Sphere::unit_sphere().bounds(), unit_box());
+        assert_eq!(Cube::new().bounds(), unit_box());
+        assert_eq!(
+            Plane::new().bounds(),
+            BoundingBox::new(
+                Point::new(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
+                Point::new(f64::INFINITY, 0., f64::INFINITY)
+            )
+        );
+        assert_eq!(
+            Cylinder::truncated(-5., 3., false).bounds(),
+            BoundingBox::new(Point::new(-1., -5., -1.), Point::new(1., 3., 1.))
+        );
+        assert_eq!(
+            Cone::truncated(-5., 3., false).bounds(),
+            BoundingBox::new(Point::new(-5., -5., -5.), Point::new(5., 3., 5.))
+        );
+        assert!(!Cylinder::new().bounds().is_finite());
+        assert_eq!(
+            Triangle::new(
+                Point::new(-3., 7., 2.),
+                Point::new(6., 2., -4.),
+                Point::new(2., -1., -1.)
+            )
+            .bounds(),
+            BoundingBox::new(Point::new(-3., -1., -4.), Point::new(6., 7., 2.))
+        );
+    }
+
+    #[test]
+    fn test_split_at_median() {
+        // Spread out most along y, so that is the axis split on
+        let boxes = [0., 9., 3., 6.]
+            .map(|y| BoundingBox::new(Point::new(0., y, 0.), Point::new(1., y + 1., 1.)));
+        let (low, high) = split_at_median(boxes.to_vec(), |bounds| *bounds);
+
+        assert_eq!(low, vec![boxes[0], boxes[2]]);
+        assert_eq!(high, vec![boxes[3], boxes[1]]);
+    }
+
+    #[test]
+    fn test_parent_space_bounds() {
+        let sphere = Matrix4::translation(1., -3., 5.)
+            * Matrix4::scaling(0.5, 2., 4.)
+            * Sphere::unit_sphere();
+        let bounds = sphere.parent_space_bounds();
+
+        assert_eq!(bounds.min, Point::new(0.5, -5., 1.));
+        assert_eq!(bounds.max, Point::new(1.5, -1., 9.));
+    }
+}
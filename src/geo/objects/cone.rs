@@ -0,0 +1,233 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector, EPSILON},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
+
+/// A double-napped cone around the y axis in object space whose radius equals
+/// `|y|`, optionally cut off at `minimum` and `maximum` (exclusive) and capped
+/// when `closed`.
+pub struct Cone {
+    transformation: ShapeTransform,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Cone {
+    /// An infinitely long, open double cone.
+    pub fn new() -> Self {
+        Cone::truncated(f64::NEG_INFINITY, f64::INFINITY, false)
+    }
+
+    pub fn truncated(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Cone {
+            transformation: ShapeTransform::default(),
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    /// Whether the point `t` along `ray` lies within `radius` of a cap.
+    fn check_cap(ray: &Ray, t: f64, radius: f64) -> bool {
+        let x = ray.origin.x + t * ray.direction.x;
+        let z = ray.origin.z + t * ray.direction.z;
+        x * x + z * z <= radius * radius
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+        for cap in [self.minimum, self.maximum] {
+            let t = (cap - ray.origin.y) / ray.direction.y;
+            if Self::check_cap(ray, t, cap.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+
+    fn push_if_in_bounds<'a>(&'a self, ray: &Ray, t: f64, xs: &mut Vec<Intersection<'a>>) {
+        let y = ray.origin.y + t * ray.direction.y;
+        if self.minimum < y && y < self.maximum {
+            xs.push(Intersection::new(t, self));
+        }
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Cone::new()
+    }
+}
+
+impl Geometry for Cone {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Point::new(-limit, self.minimum, -limit),
+            Point::new(limit, self.maximum, limit),
+        )
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+        let (origin, direction) = (ray.origin, ray.direction);
+        let mut xs = vec![];
+
+        let a = direction.x.powi(2) - direction.y.powi(2) + direction.z.powi(2);
+        let b =
+            2. * origin.x * direction.x - 2. * origin.y * direction.y + 2. * origin.z * direction.z;
+        let c = origin.x.powi(2) - origin.y.powi(2) + origin.z.powi(2);
+
+        if a.abs() < EPSILON {
+            // Parallel to one of the cone's halves, so it crosses the other once
+            if b.abs() >= EPSILON {
+                self.push_if_in_bounds(&ray, -c / (2. * b), &mut xs);
+            }
+        } else {
+            let discriminant = b * b - 4. * a * c;
+            if discriminant >= 0. {
+                let t0 = (-b - discriminant.sqrt()) / (2. * a);
+                let t1 = (-b + discriminant.sqrt()) / (2. * a);
+                self.push_if_in_bounds(&ray, t0, &mut xs);
+                self.push_if_in_bounds(&ray, t1, &mut xs);
+            }
+        }
+
+        self.intersect_caps(&ray, &mut xs);
+        Ok(Intersections::new(xs))
+    }
+
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        let p = self.world_to_object(point)?;
+
+        let distance = p.x.powi(2) + p.z.powi(2);
+        let object_normal = if distance < self.maximum.powi(2) && p.y >= self.maximum - EPSILON {
+            Vector::new(0., 1., 0.)
+        } else if distance < self.minimum.powi(2) && p.y <= self.minimum + EPSILON {
+            Vector::new(0., -1., 0.)
+        } else {
+            let y = distance.sqrt();
+            Vector::new(p.x, if p.y > 0. { -y } else { y }, p.z)
+        };
+
+        self.normal_to_world(object_normal)
+    }
+}
+
+impl Mul<Cone> for Matrix4 {
+    type Output = Cone;
+
+    fn mul(self, rhs: Cone) -> Cone {
+        let mut shape = rhs;
+        shape.set_transform(self);
+        shape
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    #[test]
+    fn test_ray_strikes_cone() {
+        let cone = Cone::new();
+        let cases = [
+            (Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 5., 5.),
+            (
+                Point::new(0., 0., -5.),
+                Vector::new(1., 1., 1.),
+                8.66025,
+                8.66025,
+            ),
+            (
+                Point::new(1., 1., -5.),
+                Vector::new(-0.5, -1., 1.),
+                4.55006,
+                49.44994,
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            let xs = cone.intersect(&ray).unwrap();
+            assert_eq!(xs.len(), 2);
+            assert_eq!(xs[0].t.specific_round(5), t0);
+            assert_eq!(xs[1].t.specific_round(5), t1);
+        }
+    }
+
+    #[test]
+    fn test_ray_parallel_to_one_half() {
+        let cone = Cone::new();
+        let ray = Ray::new(Point::new(0., 0., -1.), Vector::new(0., 1., 1.).norm());
+        let xs = cone.intersect(&ray).unwrap();
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t.specific_round(5), 0.35355);
+    }
+
+    #[test]
+    fn test_closed_cone_caps() {
+        let cone = Cone::truncated(-0.5, 0.5, true);
+        let cases = [
+            (Point::new(0., 0., -5.), Vector::new(0., 1., 0.), 0),
+            (Point::new(0., 0., -0.25), Vector::new(0., 1., 1.), 2),
+            (Point::new(0., 0., -0.25), Vector::new(0., 1., 0.), 4),
+        ];
+
+        for (origin, direction, count) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            assert_eq!(cone.intersect(&ray).unwrap().len(), count);
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let cone = Cone::new();
+        let cases = [
+            (Point::new(1., 1., 1.), Vector::new(1., -f64::sqrt(2.), 1.)),
+            (Point::new(-1., -1., 0.), Vector::new(-1., 1., 0.)),
+        ];
+
+        for (point, normal) in cases {
+            let expected = normal.norm();
+            let actual = cone.normal(point).unwrap();
+            assert_eq!(actual.x.specific_round(5), expected.x.specific_round(5));
+            assert_eq!(actual.y.specific_round(5), expected.y.specific_round(5));
+            assert_eq!(actual.z.specific_round(5), expected.z.specific_round(5));
+        }
+    }
+
+    #[test]
+    fn test_normal_on_caps() {
+        let cone = Cone::truncated(-1., 2., true);
+
+        assert_eq!(
+            cone.normal(Point::new(0.5, 2., 0.)).unwrap(),
+            Vector::new(0., 1., 0.)
+        );
+        assert_eq!(
+            cone.normal(Point::new(0., -1., 0.5)).unwrap(),
+            Vector::new(0., -1., 0.)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::Intersections,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Constructive solid geometry: two shapes combined by a set operation.
+pub struct Csg {
+    transformation: ShapeTransform,
+    pub operation: CsgOperation,
+    left: Box<dyn Geometry>,
+    right: Box<dyn Geometry>,
+}
+
+impl Csg {
+    pub fn new<L: Geometry + 'static, R: Geometry + 'static>(
+        operation: CsgOperation,
+        left: L,
+        right: R,
+    ) -> Self {
+        Csg {
+            transformation: ShapeTransform::default(),
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn union<L: Geometry + 'static, R: Geometry + 'static>(left: L, right: R) -> Self {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection<L: Geometry + 'static, R: Geometry + 'static>(left: L, right: R) -> Self {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference<L: Geometry + 'static, R: Geometry + 'static>(left: L, right: R) -> Self {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+
+    pub fn left(&self) -> &dyn Geometry {
+        &*self.left
+    }
+
+    pub fn right(&self) -> &dyn Geometry {
+        &*self.right
+    }
+
+    /// Whether a hit on the left (`left_hit`) or right shape is part of the
+    /// combined surface, given whether the ray is currently inside the left
+    /// and right shapes.
+    pub fn intersection_allowed(
+        operation: CsgOperation,
+        left_hit: bool,
+        inside_left: bool,
+        inside_right: bool,
+    ) -> bool {
+        match operation {
+            CsgOperation::Union => (left_hit && !inside_right) || (!left_hit && !inside_left),
+            CsgOperation::Intersection => (left_hit && inside_right) || (!left_hit && inside_left),
+            CsgOperation::Difference => (left_hit && !inside_right) || (!left_hit && inside_left),
+        }
+    }
+
+    /// Keeps the intersections that lie on the surface of the combined shape.
+    pub fn filter_intersections<'a>(&self, intersections: Intersections<'a>) -> Intersections<'a> {
+        let mut inside_left = false;
+        let mut inside_right = false;
+        let mut result = vec![];
+
+        for intersection in intersections {
+            let left_hit = self.left.includes(intersection.object);
+            if Self::intersection_allowed(self.operation, left_hit, inside_left, inside_right) {
+                result.push(intersection);
+            }
+            // Every hit on a child crosses its surface, toggling whether we are inside it
+            if left_hit {
+                inside_left = !inside_left;
+            } else {
+                inside_right = !inside_right;
+            }
+        }
+
+        Intersections::new(result)
+    }
+
+    fn update_children(&mut self) {
+        let world = self.transformation.world();
+        self.left.set_parent_transform(world);
+        self.right.set_parent_transform(world);
+    }
+}
+
+impl Geometry for Csg {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = self.left.parent_space_bounds();
+        bounds.merge(&self.right.parent_space_bounds());
+        bounds
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.left.divide(threshold);
+        self.right.divide(threshold);
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.left.triangle_count() + self.right.triangle_count()
+    }
+
+    fn includes(&self, other: &dyn Geometry) -> bool {
+        std::ptr::addr_eq(self as *const Self, other as *const dyn Geometry)
+            || self.left.includes(other)
+            || self.right.includes(other)
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        let local = transform * self.transformation.local();
+        self.transformation.set_local(local);
+        self.update_children();
+    }
+
+    fn set_parent_transform(&mut self, parent: Matrix4) {
+        self.transformation.set_parent(parent);
+        self.update_children();
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+        let mut intersections = self.left.intersect(&ray)?;
+        intersections.extend(self.right.intersect(&ray)?);
+        Ok(self.filter_intersections(intersections))
+    }
+
+    fn normal(&self, _point: Point) -> Result<Vector, StingrayError> {
+        Err(StingrayError::Unsupported(
+            "Csg has no surface, normals come from its children".to_string(),
+        ))
+    }
+}
+
+impl Mul<Csg> for Matrix4 {
+    type Output = Csg;
+
+    fn mul(self, rhs: Csg) -> Csg {
+        let mut csg = rhs;
+        csg.set_transform(self);
+        csg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geo::objects::{
+            cube::Cube, cylinder::Cylinder, intersection::Intersection, sphere::Sphere,
+        },
+        test::utils::ts,
+    };
+
+    #[test]
+    fn test_intersection_allowed() {
+        use CsgOperation::*;
+        let cases = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+
+        for (operation, left_hit, inside_left, inside_right, allowed) in cases {
+            assert_eq!(
+                Csg::intersection_allowed(operation, left_hit, inside_left, inside_right),
+                allowed,
+                "{operation:?} {left_hit} {inside_left} {inside_right}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_intersections() {
+        let cases = [
+            (CsgOperation::Union, vec![1., 4.]),
+            (CsgOperation::Intersection, vec![2., 3.]),
+            (CsgOperation::Difference, vec![1., 2.]),
+        ];
+
+        for (operation, expected) in cases {
+            let csg = Csg::new(operation, Sphere::unit_sphere(), Cube::new());
+            let (left, right) = (csg.left(), csg.right());
+            let xs = Intersections::new(vec![
+                Intersection::new(1., left),
+                Intersection::new(2., right),
+                Intersection::new(3., left),
+                Intersection::new(4., right),
+            ]);
+
+            assert_eq!(ts(&csg.filter_intersections(xs)), expected);
+        }
+    }
+
+    #[test]
+    fn test_ray_misses_csg() {
+        let csg = Csg::union(Sphere::unit_sphere(), Cube::new());
+        let ray = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.));
+
+        assert!(csg.intersect(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ray_hits_csg() {
+        let csg = Csg::union(
+            Sphere::unit_sphere(),
+            Matrix4::translation(0., 0., 0.5) * Sphere::unit_sphere(),
+        );
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = csg.intersect(&ray).unwrap();
+
+        assert_eq!(ts(&xs), vec![4., 6.5]);
+        assert!(std::ptr::addr_eq(xs[0].object, csg.left()));
+        assert!(std::ptr::addr_eq(xs[1].object, csg.right()));
+    }
+
+    #[test]
+    fn test_csg_bounds() {
+        let csg = Csg::difference(
+            Sphere::unit_sphere(),
+            Matrix4::translation(2., 3., 4.) * Sphere::unit_sphere(),
+        );
+
+        assert_eq!(csg.bounds().min, Point::new(-1., -1., -1.));
+        assert_eq!(csg.bounds().max, Point::new(3., 4., 5.));
+    }
+
+    #[test]
+    fn test_sphere_with_cylindrical_hole() {
+        let hole = Matrix4::scaling(0.5, 1., 0.5) * Cylinder::truncated(-2., 2., true);
+        let csg = Matrix4::translation(0., 0., 10.) * Csg::difference(Sphere::unit_sphere(), hole);
+
+        // Straight down the hole, never touching what is left of the sphere
+        let through = Ray::new(Point::new(0., -5., 10.), Vector::new(0., 1., 0.));
+        assert!(csg.intersect(&through).unwrap().is_empty());
+
+        // Across the sphere, passing through the walls of the hole
+        let across = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
+        let xs = csg.intersect(&across).unwrap();
+        assert_eq!(ts(&xs), vec![4., 4.5, 5.5, 6.]);
+        assert!(std::ptr::addr_eq(xs[1].object, csg.right()));
+        assert_eq!(
+            xs[1].object.normal(Point::new(0., 0., 9.5)).unwrap(),
+            Vector::new(0., 0., -1.)
+        );
+    }
+}
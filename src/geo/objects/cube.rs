@@ -0,0 +1,223 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector, EPSILON},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
+
+/// An axis-aligned cube spanning -1 to 1 on every axis in object space.
+pub struct Cube {
+    transformation: ShapeTransform,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Cube {
+            transformation: ShapeTransform::default(),
+        }
+    }
+
+    /// Where a ray enters and leaves the slab between -1 and 1 on one axis.
+    fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
+        let tmin_numerator = -1. - origin;
+        let tmax_numerator = 1. - origin;
+
+        let (tmin, tmax) = if direction.abs() >= EPSILON {
+            (tmin_numerator / direction, tmax_numerator / direction)
+        } else {
+            // Parallel to the slab: either always inside it or never
+            (
+                tmin_numerator * f64::INFINITY,
+                tmax_numerator * f64::INFINITY,
+            )
+        };
+
+        if tmin > tmax {
+            (tmax, tmin)
+        } else {
+            (tmin, tmax)
+        }
+    }
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Cube::new()
+    }
+}
+
+impl Geometry for Cube {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.))
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+
+        let (xtmin, xtmax) = Self::check_axis(ray.origin.x, ray.direction.x);
+        let (ytmin, ytmax) = Self::check_axis(ray.origin.y, ray.direction.y);
+        let (ztmin, ztmax) = Self::check_axis(ray.origin.z, ray.direction.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return Ok(Intersections::empty());
+        }
+
+        Ok(Intersections::new(vec![
+            Intersection::new(tmin, self),
+            Intersection::new(tmax, self),
+        ]))
+    }
+
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        let object_point = self.world_to_object(point)?;
+
+        // The face is the axis with the largest component; on edges and
+        // corners the first of the tied axes wins
+        let (x, y, z) = (
+            object_point.x.abs(),
+            object_point.y.abs(),
+            object_point.z.abs(),
+        );
+        let max = x.max(y).max(z);
+        let object_normal = if max == x {
+            Vector::new(object_point.x, 0., 0.)
+        } else if max == y {
+            Vector::new(0., object_point.y, 0.)
+        } else {
+            Vector::new(0., 0., object_point.z)
+        };
+
+        self.normal_to_world(object_normal)
+    }
+}
+
+impl Mul<Cube> for Matrix4 {
+    type Output = Cube;
+
+    fn mul(self, rhs: Cube) -> Cube {
+        let mut shape = rhs;
+        shape.set_transform(self);
+        shape
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{geo::matrix::Rotation, test::utils::ts, traits::SpecificRound};
+
+    #[test]
+    fn test_ray_intersects_cube() {
+        let cube = Cube::new();
+        let cases = [
+            (Point::new(5., 0.5, 0.), Vector::new(-1., 0., 0.), 4., 6.),
+            (Point::new(-5., 0.5, 0.), Vector::new(1., 0., 0.), 4., 6.),
+            (Point::new(0.5, 5., 0.), Vector::new(0., -1., 0.), 4., 6.),
+            (Point::new(0.5, -5., 0.), Vector::new(0., 1., 0.), 4., 6.),
+            (Point::new(0.5, 0., 5.), Vector::new(0., 0., -1.), 4., 6.),
+            (Point::new(0.5, 0., -5.), Vector::new(0., 0., 1.), 4., 6.),
+            (Point::new(0., 0.5, 0.), Vector::new(0., 0., 1.), -1., 1.),
+        ];
+
+        for (origin, direction, t1, t2) in cases {
+            let ray = Ray::new(origin, direction);
+            assert_eq!(ts(&cube.intersect(&ray).unwrap()), vec![t1, t2]);
+        }
+    }
+
+    #[test]
+    fn test_ray_misses_cube() {
+        let cube = Cube::new();
+        let cases = [
+            (Point::new(-2., 0., 0.), Vector::new(0.2673, 0.5345, 0.8018)),
+            (Point::new(0., -2., 0.), Vector::new(0.8018, 0.2673, 0.5345)),
+            (Point::new(0., 0., -2.), Vector::new(0.5345, 0.8018, 0.2673)),
+            (Point::new(2., 0., 2.), Vector::new(0., 0., -1.)),
+            (Point::new(0., 2., 2.), Vector::new(0., -1., 0.)),
+            (Point::new(2., 2., 0.), Vector::new(-1., 0., 0.)),
+        ];
+
+        for (origin, direction) in cases {
+            let ray = Ray::new(origin, direction);
+            assert!(cube.intersect(&ray).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let cube = Cube::new();
+        let cases = [
+            (Point::new(1., 0.5, -0.8), Vector::new(1., 0., 0.)),
+            (Point::new(-1., -0.2, 0.9), Vector::new(-1., 0., 0.)),
+            (Point::new(-0.4, 1., -0.1), Vector::new(0., 1., 0.)),
+            (Point::new(0.3, -1., -0.7), Vector::new(0., -1., 0.)),
+            (Point::new(-0.6, 0.3, 1.), Vector::new(0., 0., 1.)),
+            (Point::new(0.4, 0.4, -1.), Vector::new(0., 0., -1.)),
+        ];
+
+        for (point, normal) in cases {
+            assert_eq!(cube.normal(point).unwrap(), normal);
+        }
+    }
+
+    #[test]
+    fn test_normal_edges_and_corners() {
+        let cube = Cube::new();
+
+        assert_eq!(
+            cube.normal(Point::new(1., 1., 1.)).unwrap(),
+            Vector::new(1., 0., 0.)
+        );
+        assert_eq!(
+            cube.normal(Point::new(-1., -1., -1.)).unwrap(),
+            Vector::new(-1., 0., 0.)
+        );
+        assert_eq!(
+            cube.normal(Point::new(0.5, 1., -1.)).unwrap(),
+            Vector::new(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    fn test_transformed_cube() {
+        let cube = Matrix4::translation(0., 0., 5.)
+            * Matrix4::rotation(PI / 4., Rotation::Y)
+            * Matrix4::scaling(2., 2., 2.)
+            * Cube::new();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let xs = cube.intersect(&ray).unwrap();
+
+        // The rotated cube presents an edge, 2 * sqrt(2) from its center
+        let edge = 2. * f64::sqrt(2.);
+        assert_eq!(xs[0].t.specific_round(5), (5. - edge).specific_round(5));
+        assert_eq!(xs[1].t.specific_round(5), (5. + edge).specific_round(5));
+    }
+
+    #[test]
+    fn test_transformed_cube_normal() {
+        let mut cube = Cube::new();
+        cube.set_transform(Matrix4::scaling(1., 4., 1.));
+        let normal = cube.normal(Point::new(0.5, 4., 0.)).unwrap();
+
+        assert_eq!(normal, Vector::new(0., 1., 0.));
+    }
+}
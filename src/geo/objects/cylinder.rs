@@ -0,0 +1,261 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector, EPSILON},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
+
+/// A cylinder of radius 1 around the y axis in object space, optionally cut
+/// off at `minimum` and `maximum` (exclusive) and capped when `closed`.
+pub struct Cylinder {
+    transformation: ShapeTransform,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    /// An infinitely long, open cylinder.
+    pub fn new() -> Self {
+        Cylinder::truncated(f64::NEG_INFINITY, f64::INFINITY, false)
+    }
+
+    pub fn truncated(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Cylinder {
+            transformation: ShapeTransform::default(),
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    /// Whether the point `t` along `ray` lies within the unit radius of a cap.
+    fn check_cap(ray: &Ray, t: f64) -> bool {
+        let x = ray.origin.x + t * ray.direction.x;
+        let z = ray.origin.z + t * ray.direction.z;
+        x * x + z * z <= 1.
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+        for cap in [self.minimum, self.maximum] {
+            let t = (cap - ray.origin.y) / ray.direction.y;
+            if Self::check_cap(ray, t) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Cylinder::new()
+    }
+}
+
+impl Geometry for Cylinder {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-1., self.minimum, -1.),
+            Point::new(1., self.maximum, 1.),
+        )
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+        let mut xs = vec![];
+
+        let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
+        // A ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2. * ray.origin.x * ray.direction.x + 2. * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x.powi(2) + ray.origin.z.powi(2) - 1.;
+            let discriminant = b * b - 4. * a * c;
+
+            if discriminant >= 0. {
+                let t0 = (-b - discriminant.sqrt()) / (2. * a);
+                let t1 = (-b + discriminant.sqrt()) / (2. * a);
+                for t in [t0, t1] {
+                    let y = ray.origin.y + t * ray.direction.y;
+                    if self.minimum < y && y < self.maximum {
+                        xs.push(Intersection::new(t, self));
+                    }
+                }
+            }
+        }
+
+        self.intersect_caps(&ray, &mut xs);
+        Ok(Intersections::new(xs))
+    }
+
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        let object_point = self.world_to_object(point)?;
+
+        let distance = object_point.x.powi(2) + object_point.z.powi(2);
+        let object_normal = if distance < 1. && object_point.y >= self.maximum - EPSILON {
+            Vector::new(0., 1., 0.)
+        } else if distance < 1. && object_point.y <= self.minimum + EPSILON {
+            Vector::new(0., -1., 0.)
+        } else {
+            Vector::new(object_point.x, 0., object_point.z)
+        };
+
+        self.normal_to_world(object_normal)
+    }
+}
+
+impl Mul<Cylinder> for Matrix4 {
+    type Output = Cylinder;
+
+    fn mul(self, rhs: Cylinder) -> Cylinder {
+        let mut shape = rhs;
+        shape.set_transform(self);
+        shape
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test::utils::ts, traits::SpecificRound};
+
+    #[test]
+    fn test_ray_misses_cylinder() {
+        let cylinder = Cylinder::new();
+        let cases = [
+            (Point::new(1., 0., 0.), Vector::new(0., 1., 0.)),
+            (Point::new(0., 0., 0.), Vector::new(0., 1., 0.)),
+            (Point::new(0., 0., -5.), Vector::new(1., 1., 1.)),
+        ];
+
+        for (origin, direction) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            assert!(cylinder.intersect(&ray).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_ray_strikes_cylinder() {
+        let cylinder = Cylinder::new();
+        let cases = [
+            (Point::new(1., 0., -5.), Vector::new(0., 0., 1.), 5., 5.),
+            (Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 4., 6.),
+            (
+                Point::new(0.5, 0., -5.),
+                Vector::new(0.1, 1., 1.),
+                6.80798,
+                7.08872,
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            let xs = cylinder.intersect(&ray).unwrap();
+            assert_eq!(xs.len(), 2);
+            assert_eq!(xs[0].t.specific_round(5), t0);
+            assert_eq!(xs[1].t.specific_round(5), t1);
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let cylinder = Cylinder::new();
+        let cases = [
+            (Point::new(1., 0., 0.), Vector::new(1., 0., 0.)),
+            (Point::new(0., 5., -1.), Vector::new(0., 0., -1.)),
+            (Point::new(0., -2., 1.), Vector::new(0., 0., 1.)),
+            (Point::new(-1., 1., 0.), Vector::new(-1., 0., 0.)),
+        ];
+
+        for (point, normal) in cases {
+            assert_eq!(cylinder.normal(point).unwrap(), normal);
+        }
+    }
+
+    #[test]
+    fn test_truncated_cylinder() {
+        let cylinder = Cylinder::truncated(1., 2., false);
+        let cases = [
+            (Point::new(0., 1.5, 0.), Vector::new(0.1, 1., 0.), 0),
+            (Point::new(0., 3., -5.), Vector::new(0., 0., 1.), 0),
+            (Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 0),
+            (Point::new(0., 2., -5.), Vector::new(0., 0., 1.), 0),
+            (Point::new(0., 1., -5.), Vector::new(0., 0., 1.), 0),
+            (Point::new(0., 1.5, -2.), Vector::new(0., 0., 1.), 2),
+        ];
+
+        for (origin, direction, count) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            assert_eq!(cylinder.intersect(&ray).unwrap().len(), count);
+        }
+    }
+
+    #[test]
+    fn test_closed_cylinder_caps() {
+        let cylinder = Cylinder::truncated(1., 2., true);
+        let cases = [
+            (Point::new(0., 3., 0.), Vector::new(0., -1., 0.), 2),
+            (Point::new(0., 3., -2.), Vector::new(0., -1., 2.), 2),
+            // The ray leaves through the corner where side and cap meet
+            (Point::new(0., 4., -2.), Vector::new(0., -1., 1.), 2),
+            (Point::new(0., 0., -2.), Vector::new(0., 1., 2.), 2),
+            (Point::new(0., -1., -2.), Vector::new(0., 1., 1.), 2),
+        ];
+
+        for (origin, direction, count) in cases {
+            let ray = Ray::new(origin, direction.norm());
+            assert_eq!(cylinder.intersect(&ray).unwrap().len(), count);
+        }
+    }
+
+    #[test]
+    fn test_parallel_ray_through_caps() {
+        let cylinder = Cylinder::truncated(1., 2., true);
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 1., 0.));
+
+        assert_eq!(ts(&cylinder.intersect(&ray).unwrap()), vec![1., 2.]);
+    }
+
+    #[test]
+    fn test_normal_on_caps() {
+        let cylinder = Cylinder::truncated(1., 2., true);
+        let cases = [
+            (Point::new(0., 1., 0.), Vector::new(0., -1., 0.)),
+            (Point::new(0.5, 1., 0.), Vector::new(0., -1., 0.)),
+            (Point::new(0., 1., 0.5), Vector::new(0., -1., 0.)),
+            (Point::new(0., 2., 0.), Vector::new(0., 1., 0.)),
+            (Point::new(0.5, 2., 0.), Vector::new(0., 1., 0.)),
+            (Point::new(0., 2., 0.5), Vector::new(0., 1., 0.)),
+        ];
+
+        for (point, normal) in cases {
+            assert_eq!(cylinder.normal(point).unwrap(), normal);
+        }
+    }
+
+    #[test]
+    fn test_transformed_cylinder() {
+        let cylinder = Matrix4::scaling(2., 1., 2.) * Cylinder::truncated(-1., 1., true);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+
+        assert_eq!(ts(&cylinder.intersect(&ray).unwrap()), vec![3., 7.]);
+        assert_eq!(cylinder.minimum, -1.);
+        assert!(cylinder.closed);
+    }
+}
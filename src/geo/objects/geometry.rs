@@ -1,7 +1,213 @@
-use crate::geo::{matrix::Matrix, point::Point, ray::Ray, vector::Vector};
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector},
+};
+
+use super::{
+    bounds::BoundingBox,
+    intersection::{Intersection, Intersections},
+};
+
+/// Where a shape sits in the world: its own transformation and the combined
+/// transformation of every group it is nested in. The inverses that rays
+/// and normals need are worked out once whenever either changes, rather
+/// than on every intersection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeTransform {
+    local: Matrix4,
+    parent: Matrix4,
+    world: Matrix4,
+    local_inverse: Option<Matrix4>,
+    world_inverse: Option<Matrix4>,
+    world_inverse_transpose: Option<Matrix4>,
+}
+
+impl ShapeTransform {
+    pub fn new(local: Matrix4) -> Self {
+        let mut transform = ShapeTransform {
+            local,
+            parent: Matrix4::identity(),
+            world: local,
+            local_inverse: None,
+            world_inverse: None,
+            world_inverse_transpose: None,
+        };
+        transform.update();
+        transform
+    }
+
+    pub fn local(&self) -> Matrix4 {
+        self.local
+    }
+
+    pub fn parent(&self) -> Matrix4 {
+        self.parent
+    }
+
+    /// The transformation from object space all the way to world space.
+    pub fn world(&self) -> Matrix4 {
+        self.world
+    }
+
+    /// Takes rays from the parent's space into object space.
+    pub fn local_inverse(&self) -> Result<Matrix4, StingrayError> {
+        self.local_inverse.ok_or_else(not_invertible)
+    }
+
+    /// Takes points from world space into object space.
+    pub fn world_inverse(&self) -> Result<Matrix4, StingrayError> {
+        self.world_inverse.ok_or_else(not_invertible)
+    }
+
+    /// Takes object space normals into world space.
+    pub fn world_inverse_transpose(&self) -> Result<Matrix4, StingrayError> {
+        self.world_inverse_transpose.ok_or_else(not_invertible)
+    }
+
+    pub fn set_local(&mut self, local: Matrix4) {
+        self.local = local;
+        self.update();
+    }
+
+    pub fn set_parent(&mut self, parent: Matrix4) {
+        self.parent = parent;
+        self.update();
+    }
+
+    fn update(&mut self) {
+        self.world = self.parent * self.local;
+        self.local_inverse = self.local.inverse().ok();
+        self.world_inverse = self.world.inverse().ok();
+        self.world_inverse_transpose = self.world_inverse.map(|inverse| inverse.transpose());
+    }
+}
+
+/// Singular transforms, such as a scale of zero, are only reported once a
+/// ray or normal needs the inverse.
+fn not_invertible() -> StingrayError {
+    StingrayError::Singular.context("shape transformation")
+}
+
+impl Default for ShapeTransform {
+    fn default() -> Self {
+        ShapeTransform::new(Matrix4::identity())
+    }
+}
 
 pub trait Geometry {
-    fn set_transform(&mut self, transform: Matrix);
-    fn intersect(&self, ray: &Ray) -> Result<Vec<f64>, String>;
-    fn normal(&self, point: Point) -> Result<Vector, String>;
+    fn shape_transform(&self) -> &ShapeTransform;
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform;
+
+    /// Intersects a ray given in the space of the shape's parent.
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError>;
+
+    /// The world space normal at a world space `point`.
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError>;
+
+    /// The box enclosing the shape in object space.
+    fn bounds(&self) -> BoundingBox;
+
+    /// The box enclosing the shape in the space of its parent.
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.shape_transform().local())
+    }
+
+    /// Splits composite shapes into a bounding volume hierarchy with at most
+    /// `threshold` children per group. Primitives have nothing to divide.
+    fn divide(&mut self, _threshold: usize) {}
+
+    /// How many triangles make up this shape, counting through composites.
+    fn triangle_count(&self) -> usize {
+        0
+    }
+
+    /// The normal at `point` for a specific hit, for shapes whose normal
+    /// depends on where the hit landed on the surface.
+    fn normal_at_hit(&self, point: Point, _hit: &Intersection) -> Result<Vector, StingrayError> {
+        self.normal(point)
+    }
+
+    /// Whether `other` is this shape or, for composite shapes, one of its descendants.
+    fn includes(&self, other: &dyn Geometry) -> bool {
+        std::ptr::addr_eq(self as *const Self, other as *const dyn Geometry)
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        let local = transform * self.shape_transform().local();
+        self.shape_transform_mut().set_local(local);
+    }
+
+    /// Records the combined transformation of the groups containing the shape.
+    fn set_parent_transform(&mut self, parent: Matrix4) {
+        self.shape_transform_mut().set_parent(parent);
+    }
+
+    /// Converts a world space point into object space, through every parent group.
+    fn world_to_object(&self, point: Point) -> Result<Point, StingrayError> {
+        Ok(self.shape_transform().world_inverse()? * point)
+    }
+
+    /// Converts an object space normal into world space, through every parent group.
+    fn normal_to_world(&self, normal: Vector) -> Result<Vector, StingrayError> {
+        let world_normal = self.shape_transform().world_inverse_transpose()? * normal;
+        Ok(world_normal.norm())
+    }
+}
+
+impl<G: Geometry + ?Sized> Geometry for Box<G> {
+    fn shape_transform(&self) -> &ShapeTransform {
+        (**self).shape_transform()
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        (**self).shape_transform_mut()
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        (**self).intersect(ray)
+    }
+
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        (**self).normal(point)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        (**self).bounds()
+    }
+
+    fn parent_space_bounds(&self) -> BoundingBox {
+        (**self).parent_space_bounds()
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        (**self).divide(threshold)
+    }
+
+    fn triangle_count(&self) -> usize {
+        (**self).triangle_count()
+    }
+
+    fn normal_at_hit(&self, point: Point, hit: &Intersection) -> Result<Vector, StingrayError> {
+        (**self).normal_at_hit(point, hit)
+    }
+
+    fn includes(&self, other: &dyn Geometry) -> bool {
+        (**self).includes(other)
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        (**self).set_transform(transform)
+    }
+
+    fn set_parent_transform(&mut self, parent: Matrix4) {
+        (**self).set_parent_transform(parent)
+    }
+
+    fn world_to_object(&self, point: Point) -> Result<Point, StingrayError> {
+        (**self).world_to_object(point)
+    }
+
+    fn normal_to_world(&self, normal: Vector) -> Result<Vector, StingrayError> {
+        (**self).normal_to_world(normal)
+    }
 }
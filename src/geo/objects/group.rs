@@ -0,0 +1,390 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector},
+};
+
+use super::{
+    bounds::{split_at_median, BoundingBox},
+    geometry::{Geometry, ShapeTransform},
+    intersection::Intersections,
+};
+
+/// A collection of shapes transformed as a unit. Children keep their own
+/// transformations, applied relative to the group. The group's bounds are
+/// kept up to date as children are added, so rays that miss them can skip
+/// every child.
+#[derive(Default)]
+pub struct Group {
+    transformation: ShapeTransform,
+    children: Vec<Box<dyn Geometry>>,
+    bounds: BoundingBox,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Group::default()
+    }
+
+    pub fn add_child<G: Geometry + 'static>(&mut self, child: G) {
+        self.push_child(Box::new(child));
+    }
+
+    fn push_child(&mut self, mut child: Box<dyn Geometry>) {
+        child.set_parent_transform(self.transformation.world());
+        self.bounds.merge(&child.parent_space_bounds());
+        self.children.push(child);
+    }
+
+    pub fn children(&self) -> &[Box<dyn Geometry>] {
+        &self.children
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Splits the children with finite bounds in two with `split_at_median`
+    /// and moves each half into a new subgroup. Unbounded children such as planes
+    /// stay where they are.
+    fn split_children(&mut self) {
+        let children = std::mem::take(&mut self.children);
+        let (finite, unbounded): (Vec<_>, Vec<_>) = children
+            .into_iter()
+            .partition(|child| child.parent_space_bounds().is_finite());
+
+        self.children = unbounded;
+        if finite.len() < 2 {
+            self.children.extend(finite);
+            return;
+        }
+
+        let (left, right) = split_at_median(finite, |child| child.parent_space_bounds());
+        for half in [left, right] {
+            let mut subgroup = Group::new();
+            for child in half {
+                subgroup.push_child(child);
+            }
+            self.push_child(Box::new(subgroup));
+        }
+    }
+
+    /// Passes the group's placement in the world on to every child.
+    fn update_children(&mut self) {
+        let world = self.transformation.world();
+        for child in self.children.iter_mut() {
+            child.set_parent_transform(world);
+        }
+    }
+}
+
+impl Geometry for Group {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        if self.children.len() > threshold {
+            self.split_children();
+        }
+        for child in self.children.iter_mut() {
+            child.divide(threshold);
+        }
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| child.triangle_count())
+            .sum()
+    }
+
+    fn includes(&self, other: &dyn Geometry) -> bool {
+        std::ptr::addr_eq(self as *const Self, other as *const dyn Geometry)
+            || self.children.iter().any(|child| child.includes(other))
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        let local = transform * self.transformation.local();
+        self.transformation.set_local(local);
+        self.update_children();
+    }
+
+    fn set_parent_transform(&mut self, parent: Matrix4) {
+        self.transformation.set_parent(parent);
+        self.update_children();
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+        if !self.bounds.intersects(&ray) {
+            return Ok(Intersections::empty());
+        }
+        // Sorting once at the end beats merging in each child's hits
+        let mut intersections = vec![];
+        for child in self.children.iter() {
+            intersections.extend(child.intersect(&ray)?);
+        }
+        Ok(Intersections::new(intersections))
+    }
+
+    fn normal(&self, _point: Point) -> Result<Vector, StingrayError> {
+        Err(StingrayError::Unsupported(
+            "Group has no surface, normals come from its children".to_string(),
+        ))
+    }
+}
+
+impl Mul<Group> for Matrix4 {
+    type Output = Group;
+
+    fn mul(self, rhs: Group) -> Group {
+        let mut group = rhs;
+        group.set_transform(self);
+        group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{
+        geo::{
+            matrix::Rotation,
+            objects::{cube::Cube, hit::Hit, plane::Plane, sphere::Sphere},
+        },
+        test::utils::ts,
+        traits::SpecificRound,
+    };
+
+    #[test]
+    fn test_empty_group() {
+        let group = Group::new();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+
+        assert!(group.is_empty());
+        assert!(group.intersect(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_intersect_children() {
+        let mut group = Group::new();
+        group.add_child(Sphere::unit_sphere());
+        group.add_child(Matrix4::translation(0., 0., -3.) * Sphere::unit_sphere());
+        group.add_child(Matrix4::translation(5., 0., 0.) * Sphere::unit_sphere());
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = group.intersect(&ray).unwrap();
+
+        assert_eq!(ts(&xs), vec![1., 3., 4., 6.]);
+        assert!(std::ptr::addr_eq(xs[0].object, &*group.children()[1]));
+        assert!(std::ptr::addr_eq(xs[2].object, &*group.children()[0]));
+    }
+
+    #[test]
+    fn test_transformed_group() {
+        let mut group = Group::new();
+        group.set_transform(Matrix4::scaling(2., 2., 2.));
+        group.add_child(Matrix4::translation(5., 0., 0.) * Sphere::unit_sphere());
+        let ray = Ray::new(Point::new(10., 0., -10.), Vector::new(0., 0., 1.));
+
+        assert_eq!(group.intersect(&ray).unwrap().len(), 2);
+    }
+
+    /// A sphere nested two groups deep, along with the outer group.
+    fn nested_sphere(inner_scaling: Matrix4) -> Group {
+        let mut inner = Group::new();
+        inner.set_transform(inner_scaling);
+        inner.add_child(Matrix4::translation(5., 0., 0.) * Sphere::unit_sphere());
+        let mut outer = Group::new();
+        outer.set_transform(Matrix4::rotation(PI / 2., Rotation::Y));
+        outer.add_child(inner);
+        outer
+    }
+
+    #[test]
+    fn test_world_to_object_nested() {
+        let outer = nested_sphere(Matrix4::scaling(2., 2., 2.));
+        let ray = Ray::new(Point::new(0., 0., -20.), Vector::new(0., 0., 1.));
+        let xs = outer.intersect(&ray).unwrap();
+        let sphere = xs[0].object;
+
+        assert_eq!(ts(&xs), vec![8., 12.]);
+        let point = sphere.world_to_object(Point::new(-2., 0., -10.)).unwrap();
+        assert_eq!(point.x.specific_round(5), 0.);
+        assert_eq!(point.y.specific_round(5), 0.);
+        assert_eq!(point.z.specific_round(5), -1.);
+    }
+
+    #[test]
+    fn test_normal_nested() {
+        let outer = nested_sphere(Matrix4::scaling(1., 2., 3.));
+        let ray = Ray::new(Point::new(0., 0., -20.), Vector::new(0., 0., 1.));
+        let xs = outer.intersect(&ray).unwrap();
+        let sphere = xs[0].object;
+
+        let sqrt_3 = 3_f64.sqrt();
+        let normal = sphere
+            .normal_to_world(Vector::new(sqrt_3 / 3., sqrt_3 / 3., sqrt_3 / 3.))
+            .unwrap();
+        assert_eq!(normal.x.specific_round(4), 0.2857);
+        assert_eq!(normal.y.specific_round(4), 0.4286);
+        assert_eq!(normal.z.specific_round(4), -0.8571);
+
+        let normal = sphere.normal(Point::new(1.7321, 1.1547, -5.5774)).unwrap();
+        assert_eq!(normal.x.specific_round(3), 0.286);
+        assert_eq!(normal.y.specific_round(3), 0.429);
+        assert_eq!(normal.z.specific_round(3), -0.857);
+    }
+
+    #[test]
+    fn test_transform_after_adding_children() {
+        let mut group = Group::new();
+        group.add_child(Sphere::unit_sphere());
+        group.set_transform(Matrix4::translation(0., 3., 0.));
+        let ray = Ray::new(Point::new(0., 3., -5.), Vector::new(0., 0., 1.));
+        let xs = group.intersect(&ray).unwrap();
+
+        assert_eq!(ts(&xs), vec![4., 6.]);
+        assert_eq!(
+            xs[0].object.normal(Point::new(0., 3., -1.)).unwrap(),
+            Vector::new(0., 0., -1.)
+        );
+    }
+
+    #[test]
+    fn test_includes_descendants() {
+        let outer = nested_sphere(Matrix4::identity());
+        let ray = Ray::new(Point::new(0., 0., -20.), Vector::new(0., 0., 1.));
+        let xs = outer.intersect(&ray).unwrap();
+        let sphere = Sphere::unit_sphere();
+
+        assert!(outer.includes(xs[0].object));
+        assert!(outer.includes(&outer));
+        assert!(!outer.includes(&sphere));
+    }
+
+    #[test]
+    fn test_group_bounds() {
+        let mut group = Group::new();
+        group.add_child(
+            Matrix4::translation(2., 5., -3.)
+                * Matrix4::scaling(2., 2., 2.)
+                * Sphere::unit_sphere(),
+        );
+        group.add_child(
+            Matrix4::translation(-4., -1., 4.) * Matrix4::scaling(0.5, 1., 0.5) * Cube::new(),
+        );
+
+        assert_eq!(group.bounds().min, Point::new(-4.5, -2., -5.));
+        assert_eq!(group.bounds().max, Point::new(4., 7., 4.5));
+    }
+
+    #[test]
+    fn test_ray_missing_bounds_skips_children() {
+        let mut group = Group::new();
+        group.add_child(Sphere::unit_sphere());
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 1., 0.));
+
+        assert!(!group.bounds().intersects(&ray));
+        assert!(group.intersect(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_divide_splits_at_median() {
+        let mut group = Group::new();
+        for x in [-2., 2., -4., 4.] {
+            group.add_child(Matrix4::translation(x, 0., 0.) * Sphere::unit_sphere());
+        }
+        group.divide(1);
+
+        // Two subgroups of two, each split again into single spheres
+        assert_eq!(group.len(), 2);
+        let ray = Ray::new(Point::new(-2., 0., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(ts(&group.intersect(&ray).unwrap()), vec![4., 6.]);
+        assert_eq!(group.bounds().min, Point::new(-5., -1., -1.));
+    }
+
+    #[test]
+    fn test_divide_keeps_unbounded_children() {
+        let mut group = Group::new();
+        group.add_child(Plane::new());
+        for x in [-2., 2., -4.] {
+            group.add_child(Matrix4::translation(x, 0., 0.) * Sphere::unit_sphere());
+        }
+        group.divide(2);
+
+        assert_eq!(group.len(), 3);
+        let ray = Ray::new(Point::new(4., 5., 0.), Vector::new(0., -1., 0.));
+        assert_eq!(ts(&group.intersect(&ray).unwrap()), vec![5.]);
+    }
+
+    #[test]
+    fn test_divided_group_matches_flat_group() {
+        let build = || {
+            let mut group = Group::new();
+            for i in 0..64 {
+                let (x, z) = ((i % 8) as f64 * 1.5, (i / 8) as f64 * 1.5);
+                group.add_child(
+                    Matrix4::translation(x, 0., z) * Matrix4::scaling(0.5, 0.5, 0.5) * Cube::new(),
+                );
+            }
+            Matrix4::rotation(PI / 6., Rotation::Y) * group
+        };
+        let flat = build();
+        let mut divided = build();
+        divided.divide(4);
+
+        for i in 0..20 {
+            let ray = Ray::new(
+                Point::new(-5., 3., i as f64 * 0.6 - 2.),
+                Vector::new(1., -0.4, 0.3).norm(),
+            );
+            assert_eq!(
+                ts(&flat.intersect(&ray).unwrap()),
+                ts(&divided.intersect(&ray).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn test_table_moves_as_unit() {
+        let mut table = Group::new();
+        table.add_child(
+            Matrix4::translation(0., 1., 0.) * Matrix4::scaling(1., 0.1, 1.) * Cube::new(),
+        );
+        for (x, z) in [(-0.9, -0.9), (0.9, -0.9), (-0.9, 0.9), (0.9, 0.9)] {
+            table.add_child(
+                Matrix4::translation(x, 0.45, z) * Matrix4::scaling(0.05, 0.45, 0.05) * Cube::new(),
+            );
+        }
+        table.set_transform(Matrix4::translation(10., 0., 0.));
+
+        // Straight down onto the top, then through a leg
+        let top = Ray::new(Point::new(10., 5., 0.), Vector::new(0., -1., 0.));
+        let leg = Ray::new(Point::new(10.9, 5., 0.9), Vector::new(0., -1., 0.));
+        let miss = Ray::new(Point::new(0., 5., 0.), Vector::new(0., -1., 0.));
+
+        let xs = table.intersect(&top).unwrap();
+        assert_eq!(xs.hit().unwrap().t.specific_round(5), 3.9);
+        assert_eq!(table.intersect(&leg).unwrap().len(), 4);
+        assert!(table.intersect(&miss).unwrap().is_empty());
+
+        let normal = xs[0].object.normal(Point::new(10., 1.1, 0.)).unwrap();
+        assert_eq!(normal, Vector::new(0., 1., 0.));
+    }
+}
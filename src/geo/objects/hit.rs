@@ -1,8 +1,12 @@
 pub trait Hit {
-    fn hit(&self) -> Option<f64>;
+    type Output;
+
+    fn hit(&self) -> Option<Self::Output>;
 }
 
 impl Hit for Vec<f64> {
+    type Output = f64;
+
     fn hit(&self) -> Option<f64> {
         self.iter()
             .filter(|&&t| t >= 0.0) // Filter out negative intersections
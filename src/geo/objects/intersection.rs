@@ -0,0 +1,260 @@
+use std::ops::Deref;
+
+use crate::{
+    error::StingrayError,
+    geo::{point::Point, ray::Ray, vector::Vector, EPSILON},
+    lighting::Material,
+};
+
+use super::{geometry::Geometry, hit::Hit};
+
+/// A single crossing of a ray with a shape, `t` units along the ray. `u` and
+/// `v` locate the hit on the surface for shapes that need it, such as the
+/// barycentric coordinates on a triangle.
+#[derive(Clone, Copy)]
+pub struct Intersection<'a> {
+    pub t: f64,
+    pub object: &'a dyn Geometry,
+    pub material: Option<&'a Material>,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(t: f64, object: &'a dyn Geometry) -> Self {
+        Intersection::with_uv(t, object, 0., 0.)
+    }
+
+    pub fn with_uv(t: f64, object: &'a dyn Geometry, u: f64, v: f64) -> Self {
+        Intersection {
+            t,
+            object,
+            material: None,
+            u,
+            v,
+        }
+    }
+
+    pub fn with_material(self, material: &'a Material) -> Self {
+        Intersection {
+            material: Some(material),
+            ..self
+        }
+    }
+
+    /// Sets the material unless the hit already has one, so the material
+    /// of the innermost painted shape wins.
+    pub fn or_material(self, material: &'a Material) -> Self {
+        Intersection {
+            material: Some(self.material.unwrap_or(material)),
+            ..self
+        }
+    }
+
+    /// Precomputes the values needed to shade this intersection as seen along `ray`.
+    pub fn prepare_computations(&self, ray: &Ray) -> Result<Computations<'a>, StingrayError> {
+        let point = ray.point_at(self.t);
+        let eye = -ray.direction;
+        let mut normal = self.object.normal_at_hit(point, self)?;
+        let inside = normal.dot(eye) < 0.;
+        if inside {
+            normal = -normal;
+        }
+
+        Ok(Computations {
+            t: self.t,
+            object: self.object,
+            material: self.material,
+            point,
+            over_point: point + normal * EPSILON,
+            eye,
+            normal,
+            inside,
+        })
+    }
+}
+
+/// Shading data for an intersection. `over_point` sits just above the
+/// surface so rays cast from it do not hit the surface they start on.
+pub struct Computations<'a> {
+    pub t: f64,
+    pub object: &'a dyn Geometry,
+    pub material: Option<&'a Material>,
+    pub point: Point,
+    pub over_point: Point,
+    pub eye: Vector,
+    pub normal: Vector,
+    pub inside: bool,
+}
+
+/// A collection of intersections kept sorted by `t`.
+#[derive(Clone, Default)]
+pub struct Intersections<'a> {
+    intersections: Vec<Intersection<'a>>,
+}
+
+impl<'a> Intersections<'a> {
+    pub fn new(mut intersections: Vec<Intersection<'a>>) -> Self {
+        intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Intersections { intersections }
+    }
+
+    pub fn empty() -> Self {
+        Intersections::default()
+    }
+
+    pub fn extend(&mut self, other: Intersections<'a>) {
+        self.intersections.extend(other.intersections);
+        self.intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+}
+
+impl<'a> Deref for Intersections<'a> {
+    type Target = [Intersection<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.intersections
+    }
+}
+
+impl<'a> IntoIterator for Intersections<'a> {
+    type Item = Intersection<'a>;
+    type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.intersections.into_iter()
+    }
+}
+
+impl<'a> FromIterator<Intersection<'a>> for Intersections<'a> {
+    fn from_iter<I: IntoIterator<Item = Intersection<'a>>>(iter: I) -> Self {
+        Intersections::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a> Hit for Intersections<'a> {
+    type Output = Intersection<'a>;
+
+    fn hit(&self) -> Option<Intersection<'a>> {
+        // Sorted by t, so the first non-negative intersection is the closest
+        self.intersections.iter().find(|i| i.t >= 0.).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geo::{matrix4::Matrix4, objects::sphere::Sphere},
+        test::utils::ts,
+    };
+
+    #[test]
+    fn test_intersections_sorted() {
+        let sphere = Sphere::unit_sphere();
+        let xs = Intersections::new(vec![
+            Intersection::new(5., &sphere),
+            Intersection::new(-3., &sphere),
+            Intersection::new(2., &sphere),
+        ]);
+
+        assert_eq!(ts(&xs), vec![-3., 2., 5.]);
+    }
+
+    #[test]
+    fn test_intersection_keeps_object() {
+        let sphere = Sphere::unit_sphere();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = sphere.intersect(&ray).unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::addr_eq(xs[0].object, &sphere));
+        assert!(std::ptr::addr_eq(xs[1].object, &sphere));
+    }
+
+    #[test]
+    fn test_hit_all_positive() {
+        let sphere = Sphere::unit_sphere();
+        let xs = Intersections::new(vec![
+            Intersection::new(2., &sphere),
+            Intersection::new(1., &sphere),
+        ]);
+
+        assert_eq!(xs.hit().unwrap().t, 1.);
+    }
+
+    #[test]
+    fn test_hit_some_negative() {
+        let sphere = Sphere::unit_sphere();
+        let xs = Intersections::new(vec![
+            Intersection::new(-1., &sphere),
+            Intersection::new(1., &sphere),
+        ]);
+
+        assert_eq!(xs.hit().unwrap().t, 1.);
+    }
+
+    #[test]
+    fn test_hit_all_negative() {
+        let sphere = Sphere::unit_sphere();
+        let xs = Intersections::new(vec![
+            Intersection::new(-2., &sphere),
+            Intersection::new(-1., &sphere),
+        ]);
+
+        assert!(xs.hit().is_none());
+    }
+
+    #[test]
+    fn test_hit_lowest_non_negative() {
+        let sphere = Sphere::unit_sphere();
+        let xs: Intersections = [5., 7., -3., 2.]
+            .into_iter()
+            .map(|t| Intersection::new(t, &sphere))
+            .collect();
+
+        assert_eq!(xs.hit().unwrap().t, 2.);
+    }
+
+    #[test]
+    fn test_prepare_computations_outside() {
+        let sphere = Sphere::unit_sphere();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let comps = Intersection::new(4., &sphere)
+            .prepare_computations(&ray)
+            .unwrap();
+
+        assert_eq!(comps.t, 4.);
+        assert_eq!(comps.point, Point::new(0., 0., -1.));
+        assert_eq!(comps.eye, Vector::new(0., 0., -1.));
+        assert_eq!(comps.normal, Vector::new(0., 0., -1.));
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn test_prepare_computations_inside() {
+        let sphere = Sphere::unit_sphere();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let comps = Intersection::new(1., &sphere)
+            .prepare_computations(&ray)
+            .unwrap();
+
+        assert_eq!(comps.point, Point::new(0., 0., 1.));
+        assert_eq!(comps.eye, Vector::new(0., 0., -1.));
+        // The normal is inverted to face the eye
+        assert_eq!(comps.normal, Vector::new(0., 0., -1.));
+        assert!(comps.inside);
+    }
+
+    #[test]
+    fn test_over_point() {
+        let sphere = Matrix4::translation(0., 0., 1.) * Sphere::unit_sphere();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let comps = Intersection::new(5., &sphere)
+            .prepare_computations(&ray)
+            .unwrap();
+
+        assert!(comps.over_point.z < -EPSILON / 2.);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+}
@@ -1,3 +1,14 @@
+pub mod bounds;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
 pub mod geometry;
+pub mod group;
 pub mod hit;
+pub mod intersection;
+pub mod painted;
+pub mod plane;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod triangle;
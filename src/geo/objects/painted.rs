@@ -0,0 +1,152 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector},
+    lighting::Material,
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
+
+/// A shape with a material of its own, for children of groups and CSG
+/// shapes that should not take on the material of the object they belong
+/// to. Everything but the material is passed through to the shape.
+pub struct Painted {
+    shape: Box<dyn Geometry>,
+    pub material: Material,
+}
+
+impl Painted {
+    pub fn new<G: Geometry + 'static>(shape: G, material: Material) -> Self {
+        Painted {
+            shape: Box::new(shape),
+            material,
+        }
+    }
+}
+
+impl Geometry for Painted {
+    fn shape_transform(&self) -> &ShapeTransform {
+        self.shape.shape_transform()
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        self.shape.shape_transform_mut()
+    }
+
+    /// Hits that no nested shape has painted already take this material.
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        Ok(self
+            .shape
+            .intersect(ray)?
+            .into_iter()
+            .map(|i| i.or_material(&self.material))
+            .collect())
+    }
+
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        self.shape.normal(point)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.shape.bounds()
+    }
+
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.shape.parent_space_bounds()
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.shape.divide(threshold)
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.shape.triangle_count()
+    }
+
+    fn normal_at_hit(&self, point: Point, hit: &Intersection) -> Result<Vector, StingrayError> {
+        self.shape.normal_at_hit(point, hit)
+    }
+
+    fn includes(&self, other: &dyn Geometry) -> bool {
+        std::ptr::addr_eq(self as *const Self, other as *const dyn Geometry)
+            || self.shape.includes(other)
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.shape.set_transform(transform)
+    }
+
+    fn set_parent_transform(&mut self, parent: Matrix4) {
+        self.shape.set_parent_transform(parent)
+    }
+
+    fn world_to_object(&self, point: Point) -> Result<Point, StingrayError> {
+        self.shape.world_to_object(point)
+    }
+
+    fn normal_to_world(&self, normal: Vector) -> Result<Vector, StingrayError> {
+        self.shape.normal_to_world(normal)
+    }
+}
+
+impl Mul<Painted> for Matrix4 {
+    type Output = Painted;
+
+    fn mul(self, rhs: Painted) -> Painted {
+        let mut painted = rhs;
+        painted.set_transform(self);
+        painted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        geo::objects::{group::Group, sphere::Sphere},
+    };
+
+    fn red() -> Material {
+        Material {
+            color: Color::new(1., 0., 0.),
+            ..Material::default()
+        }
+    }
+
+    fn rgb(hit: &Intersection) -> (f64, f64, f64) {
+        let color = hit.material.unwrap().color;
+        (color.r, color.g, color.b)
+    }
+
+    #[test]
+    fn test_hits_carry_the_material() {
+        let sphere = Matrix4::translation(0., 0., 5.) * Painted::new(Sphere::unit_sphere(), red());
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let xs = sphere.intersect(&ray).unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.);
+        assert_eq!(rgb(&xs[0]), (1., 0., 0.));
+        assert_eq!(sphere.parent_space_bounds().min, Point::new(-1., -1., 4.));
+    }
+
+    #[test]
+    fn test_innermost_material_wins() {
+        let mut group = Group::new();
+        group.add_child(Painted::new(Sphere::unit_sphere(), red()));
+        group.add_child(Matrix4::translation(0., 0., 5.) * Sphere::unit_sphere());
+        let painted = Painted::new(group, Material::default());
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = painted.intersect(&ray).unwrap();
+
+        assert_eq!(rgb(&xs[0]), (1., 0., 0.));
+        assert_eq!(rgb(&xs[2]), (1., 1., 1.));
+        assert!(painted.includes(xs[0].object));
+    }
+}
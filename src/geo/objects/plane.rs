@@ -0,0 +1,155 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector, EPSILON},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
+
+/// An infinite plane spanning x and z through the origin in object space.
+pub struct Plane {
+    transformation: ShapeTransform,
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Plane {
+            transformation: ShapeTransform::default(),
+        }
+    }
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Plane::new()
+    }
+}
+
+impl Geometry for Plane {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0., f64::INFINITY),
+        )
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+
+        // A ray parallel to the plane never hits it, and a coplanar ray sees
+        // an infinitely thin surface edge-on
+        if ray.direction.y.abs() < EPSILON {
+            return Ok(Intersections::empty());
+        }
+
+        let t = -ray.origin.y / ray.direction.y;
+        Ok(Intersections::new(vec![Intersection::new(t, self)]))
+    }
+
+    fn normal(&self, _point: Point) -> Result<Vector, StingrayError> {
+        let object_normal = Vector::new(0., 1., 0.);
+        self.normal_to_world(object_normal)
+    }
+}
+
+impl Mul<Plane> for Matrix4 {
+    type Output = Plane;
+
+    fn mul(self, rhs: Plane) -> Plane {
+        let mut shape = rhs;
+        shape.set_transform(self);
+        shape
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+    use crate::{geo::matrix::Rotation, test::utils::ts, traits::SpecificRound};
+
+    #[test]
+    fn test_normal_constant() {
+        let plane = Plane::new();
+
+        for point in [
+            Point::new(0., 0., 0.),
+            Point::new(10., 0., -10.),
+            Point::new(-5., 0., 150.),
+        ] {
+            assert_eq!(plane.normal(point).unwrap(), Vector::new(0., 1., 0.));
+        }
+    }
+
+    #[test]
+    fn test_intersect_parallel() {
+        let plane = Plane::new();
+        let ray = Ray::new(Point::new(0., 10., 0.), Vector::new(0., 0., 1.));
+
+        assert!(plane.intersect(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_intersect_coplanar() {
+        let plane = Plane::new();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+
+        assert!(plane.intersect(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_intersect_from_above() {
+        let plane = Plane::new();
+        let ray = Ray::new(Point::new(0., 1., 0.), Vector::new(0., -1., 0.));
+        let xs = plane.intersect(&ray).unwrap();
+
+        assert_eq!(ts(&xs), vec![1.]);
+        assert!(std::ptr::addr_eq(xs[0].object, &plane));
+    }
+
+    #[test]
+    fn test_intersect_from_below() {
+        let plane = Plane::new();
+        let ray = Ray::new(Point::new(0., -1., 0.), Vector::new(0., 1., 0.));
+
+        assert_eq!(ts(&plane.intersect(&ray).unwrap()), vec![1.]);
+    }
+
+    #[test]
+    fn test_transformed_plane() {
+        // A wall facing -z, one unit behind the origin
+        let plane = Matrix4::translation(0., 0., 1.)
+            * Matrix4::rotation(-PI / 2., Rotation::X)
+            * Plane::new();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let normal = plane.normal(Point::new(0., 0., 1.)).unwrap();
+
+        assert_eq!(ts(&plane.intersect(&ray).unwrap()), vec![6.]);
+        assert_eq!(normal.y.specific_round(5), 0.);
+        assert_eq!(normal.z.specific_round(5), -1.);
+    }
+
+    #[test]
+    fn test_tilted_plane_normal() {
+        let mut plane = Plane::new();
+        plane.set_transform(Matrix4::rotation(PI / 4., Rotation::Z));
+        let normal = plane.normal(Point::new(0., 0., 0.)).unwrap();
+
+        assert_eq!(normal.x.specific_round(5), -FRAC_1_SQRT_2.specific_round(5));
+        assert_eq!(normal.y.specific_round(5), FRAC_1_SQRT_2.specific_round(5));
+    }
+}
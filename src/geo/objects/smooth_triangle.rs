@@ -0,0 +1,157 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+    triangle::Triangle,
+};
+
+/// A triangle with a normal per vertex, interpolated across the surface to
+/// make meshes look smooth.
+pub struct SmoothTriangle {
+    triangle: Triangle,
+    pub n1: Vector,
+    pub n2: Vector,
+    pub n3: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        SmoothTriangle {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    pub fn triangle(&self) -> &Triangle {
+        &self.triangle
+    }
+
+    /// Object space normal for the barycentric coordinates `u` and `v`.
+    fn interpolate(&self, u: f64, v: f64) -> Vector {
+        self.n2 * u + self.n3 * v + self.n1 * (1. - u - v)
+    }
+}
+
+impl Geometry for SmoothTriangle {
+    fn shape_transform(&self) -> &ShapeTransform {
+        self.triangle.shape_transform()
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        self.triangle.shape_transform_mut()
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.triangle.bounds()
+    }
+
+    fn triangle_count(&self) -> usize {
+        1
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.shape_transform().local_inverse()? * ray;
+        Ok(match self.triangle.local_intersect(&ray) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::empty(),
+        })
+    }
+
+    /// Without a hit to read `u` and `v` from, recovers them from the point.
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        let object_point = self.world_to_object(point)?;
+        let Triangle { p1, e1, e2, .. } = self.triangle;
+        let p1_to_point = object_point - p1;
+
+        let (d11, d12, d22) = (e1.dot(e1), e1.dot(e2), e2.dot(e2));
+        let (d1p, d2p) = (e1.dot(p1_to_point), e2.dot(p1_to_point));
+        let denominator = d11 * d22 - d12 * d12;
+        let u = (d22 * d1p - d12 * d2p) / denominator;
+        let v = (d11 * d2p - d12 * d1p) / denominator;
+
+        self.normal_to_world(self.interpolate(u, v))
+    }
+
+    fn normal_at_hit(&self, _point: Point, hit: &Intersection) -> Result<Vector, StingrayError> {
+        self.normal_to_world(self.interpolate(hit.u, hit.v))
+    }
+}
+
+impl Mul<SmoothTriangle> for Matrix4 {
+    type Output = SmoothTriangle;
+
+    fn mul(self, rhs: SmoothTriangle) -> SmoothTriangle {
+        let mut shape = rhs;
+        shape.set_transform(self);
+        shape
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new(0., 1., 0.),
+            Point::new(-1., 0., 0.),
+            Point::new(1., 0., 0.),
+            Vector::new(0., 1., 0.),
+            Vector::new(-1., 0., 0.),
+            Vector::new(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    fn test_intersection_stores_uv() {
+        let triangle = smooth_triangle();
+        let ray = Ray::new(Point::new(-0.2, 0.3, -2.), Vector::new(0., 0., 1.));
+        let xs = triangle.intersect(&ray).unwrap();
+
+        assert_eq!(xs[0].u.specific_round(5), 0.45);
+        assert_eq!(xs[0].v.specific_round(5), 0.25);
+    }
+
+    #[test]
+    fn test_normal_interpolated_with_uv() {
+        let triangle = smooth_triangle();
+        let hit = Intersection::with_uv(1., &triangle, 0.45, 0.25);
+        let normal = triangle
+            .normal_at_hit(Point::new(0., 0., 0.), &hit)
+            .unwrap();
+
+        assert_eq!(normal.x.specific_round(5), -0.5547);
+        assert_eq!(normal.y.specific_round(5), 0.83205);
+        assert_eq!(normal.z.specific_round(5), 0.);
+    }
+
+    #[test]
+    fn test_prepare_computations_uses_uv() {
+        let triangle = smooth_triangle();
+        let ray = Ray::new(Point::new(-0.2, 0.3, -2.), Vector::new(0., 0., 1.));
+        let hit = Intersection::with_uv(1., &triangle, 0.45, 0.25);
+        let comps = hit.prepare_computations(&ray).unwrap();
+
+        assert_eq!(comps.normal.x.specific_round(5), -0.5547);
+        assert_eq!(comps.normal.y.specific_round(5), 0.83205);
+    }
+
+    #[test]
+    fn test_normal_without_hit_matches_uv() {
+        let triangle = smooth_triangle();
+        let normal = triangle.normal(Point::new(-0.2, 0.3, 0.)).unwrap();
+
+        assert_eq!(normal.x.specific_round(5), -0.5547);
+        assert_eq!(normal.y.specific_round(5), 0.83205);
+    }
+}
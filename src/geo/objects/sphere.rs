@@ -1,13 +1,20 @@
 use std::ops::Mul;
 
-use crate::geo::{matrix::Matrix, point::Point, ray::Ray, vector::Vector};
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector},
+};
 
-use super::geometry::Geometry;
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
 
 pub struct Sphere {
     center: Point,
     radius: f64,
-    transformation: Matrix,
+    transformation: ShapeTransform,
 }
 
 impl Sphere {
@@ -15,7 +22,7 @@ impl Sphere {
         Sphere {
             center,
             radius,
-            transformation: Matrix::identity(),
+            transformation: ShapeTransform::default(),
         }
     }
 
@@ -25,13 +32,22 @@ impl Sphere {
 }
 
 impl Geometry for Sphere {
-    fn set_transform(&mut self, transformation: Matrix) {
-        self.transformation = transformation * self.transformation.clone();
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
     }
 
-    fn intersect(&self, ray: &Ray) -> Result<Vec<f64>, String> {
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let radius = Vector::new(self.radius, self.radius, self.radius);
+        BoundingBox::new(self.center - radius, self.center + radius)
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
         // Transform the ray by the inverse of the sphere's transformation
-        let inverse = self.transformation.inverse()?;
+        let inverse = self.transformation.local_inverse()?;
         let ray = inverse * ray;
 
         let oc = ray.origin - self.center;
@@ -41,55 +57,54 @@ impl Geometry for Sphere {
         let discriminant = b * b - 4. * a * c;
 
         if discriminant < 0. {
-            return Ok(vec![]);
+            return Ok(Intersections::empty());
         }
 
         let t = (-b - discriminant.sqrt()) / (2. * a);
         let t2 = (-b + discriminant.sqrt()) / (2. * a);
 
-        Ok(vec![t, t2])
+        Ok(Intersections::new(vec![
+            Intersection::new(t, self),
+            Intersection::new(t2, self),
+        ]))
     }
 
-    fn normal(&self, point: Point) -> Result<Vector, String> {
-        let transform_inverse = dbg!(self.transformation.inverse()?);
-        let object_point = transform_inverse.clone() * point;
+    fn normal(&self, point: Point) -> Result<Vector, StingrayError> {
+        let object_point = self.world_to_object(point)?;
         let object_normal = (object_point - self.center).norm();
-        let world_normal = transform_inverse.transpose()? * object_normal;
-        Ok(world_normal.norm())
+        self.normal_to_world(object_normal)
     }
 }
 
-impl Mul<&Matrix> for Sphere {
+impl Mul<Matrix4> for Sphere {
     type Output = Sphere;
 
-    fn mul(self, rhs: &Matrix) -> Sphere {
-        Sphere {
-            center: self.center,
-            radius: self.radius,
-            transformation: self.transformation * rhs.clone(),
-        }
+    fn mul(self, rhs: Matrix4) -> Sphere {
+        let mut sphere = self;
+        let local = sphere.transformation.local() * rhs;
+        sphere.transformation.set_local(local);
+        sphere
     }
 }
 
-impl Mul<Sphere> for Matrix {
+impl Mul<Sphere> for Matrix4 {
     type Output = Sphere;
 
     fn mul(self, rhs: Sphere) -> Sphere {
-        Sphere {
-            center: rhs.center,
-            radius: rhs.radius,
-            transformation: self * rhs.transformation,
-        }
+        let mut sphere = rhs;
+        sphere.set_transform(self);
+        sphere
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::f64::consts::PI;
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
 
     use super::*;
     use crate::{
         geo::{matrix::Rotation, objects::hit::Hit, ray::Ray, vector::Vector},
+        test::utils::ts,
         traits::SpecificRound,
     };
 
@@ -98,7 +113,7 @@ mod tests {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
         let ray = Ray::new(Point::new(0., 1., -5.), Vector::new(0., 0., 1.));
 
-        assert_eq!(sphere.intersect(&ray).unwrap(), vec![5., 5.]);
+        assert_eq!(ts(&sphere.intersect(&ray).unwrap()), vec![5., 5.]);
     }
 
     #[test]
@@ -106,7 +121,7 @@ mod tests {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
         let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
 
-        assert_eq!(sphere.intersect(&ray).unwrap(), vec![-1., 1.]);
+        assert_eq!(ts(&sphere.intersect(&ray).unwrap()), vec![-1., 1.]);
     }
 
     #[test]
@@ -114,7 +129,7 @@ mod tests {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
         let ray = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
 
-        assert_eq!(sphere.intersect(&ray).unwrap(), vec![-6., -4.]);
+        assert_eq!(ts(&sphere.intersect(&ray).unwrap()), vec![-6., -4.]);
     }
 
     #[test]
@@ -124,37 +139,37 @@ mod tests {
 
         let intersect = sphere.intersect(&ray).unwrap();
 
-        assert_eq!(intersect.hit(), None);
+        assert!(intersect.hit().is_none());
     }
 
     #[test]
     fn test_transformation() {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
-        let scaling = Matrix::scaling(2., 2., 2.);
-        let sphere = scaling.clone() * sphere;
+        let scaling = Matrix4::scaling(2., 2., 2.);
+        let sphere = scaling * sphere;
 
         assert_eq!(sphere.radius, 1.);
-        assert_eq!(sphere.transformation, scaling);
+        assert_eq!(sphere.transformation.local(), scaling);
     }
 
     #[test]
     fn scale_ray() {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
         let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
-        let scaling = Matrix::scaling(2., 2., 2.);
+        let scaling = Matrix4::scaling(2., 2., 2.);
         let sphere = scaling * sphere;
         let intersect = sphere.intersect(&ray).unwrap();
 
         assert_eq!(intersect.len(), 2);
-        assert_eq!(intersect[0], 3.);
-        assert_eq!(intersect[1], 7.);
+        assert_eq!(intersect[0].t, 3.);
+        assert_eq!(intersect[1].t, 7.);
     }
 
     #[test]
     fn translate_ray() {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
         let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
-        let translation = Matrix::translation(5., 0., 0.);
+        let translation = Matrix4::translation(5., 0., 0.);
         let sphere = translation * sphere;
         let intersect = sphere.intersect(&ray).unwrap();
 
@@ -194,20 +209,22 @@ mod tests {
     #[test]
     fn test_normal_transformed() {
         let sphere = Sphere::new(Point::new(0., 0., 0.), 1.0);
-        let translation = Matrix::translation(0., 1., 0.);
+        let translation = Matrix4::translation(0., 1., 0.);
 
         let sphere = translation * sphere;
-        let normal = sphere.normal(Point::new(0., 1.70711, -0.70711)).unwrap();
+        let normal = sphere
+            .normal(Point::new(0., 1. + FRAC_1_SQRT_2, -FRAC_1_SQRT_2))
+            .unwrap();
 
         assert_eq!(normal.x.specific_round(3), 0.);
-        assert_eq!(normal.y.specific_round(5), 0.70711);
-        assert_eq!(normal.z.specific_round(5), -0.70711);
+        assert_eq!(normal.y.specific_round(5), FRAC_1_SQRT_2.specific_round(5));
+        assert_eq!(normal.z.specific_round(5), -FRAC_1_SQRT_2.specific_round(5));
     }
 
     #[test]
     fn test_normal_transformed_inverse() {
         let mut sphere = Sphere::unit_sphere();
-        let m = Matrix::scaling(1., 0.5, 1.) * Matrix::rotation(PI / 5., Rotation::Z);
+        let m = Matrix4::scaling(1., 0.5, 1.) * Matrix4::rotation(PI / 5., Rotation::Z);
         sphere.set_transform(m);
         let normal = sphere
             .normal(Point::new(0., f64::sqrt(2.) / 2., -f64::sqrt(2.) / 2.))
@@ -216,4 +233,33 @@ mod tests {
         assert_eq!(normal.y.specific_round(5), 0.97014);
         assert_eq!(normal.z.specific_round(5), -0.24254);
     }
+
+    #[test]
+    fn test_cached_inverses() {
+        let mut sphere = Sphere::unit_sphere();
+        sphere.set_transform(Matrix4::scaling(2., 2., 2.));
+        sphere.set_parent_transform(Matrix4::translation(0., 1., 0.));
+        let transform = sphere.shape_transform();
+
+        let world = Matrix4::translation(0., 1., 0.) * Matrix4::scaling(2., 2., 2.);
+        assert_eq!(transform.world(), world);
+        assert_eq!(
+            transform.local_inverse().unwrap(),
+            Matrix4::scaling(0.5, 0.5, 0.5)
+        );
+        assert_eq!(transform.world_inverse().unwrap(), world.inverse().unwrap());
+        assert_eq!(
+            transform.world_inverse_transpose().unwrap(),
+            world.inverse().unwrap().transpose()
+        );
+    }
+
+    #[test]
+    fn test_singular_transform() {
+        let sphere = Matrix4::scaling(0., 1., 1.) * Sphere::unit_sphere();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+
+        assert!(sphere.intersect(&ray).is_err());
+        assert!(sphere.normal(Point::new(0., 0., -1.)).is_err());
+    }
 }
@@ -0,0 +1,184 @@
+use std::ops::Mul;
+
+use crate::{
+    error::StingrayError,
+    geo::{matrix4::Matrix4, point::Point, ray::Ray, vector::Vector, EPSILON},
+};
+
+use super::{
+    bounds::BoundingBox,
+    geometry::{Geometry, ShapeTransform},
+    intersection::{Intersection, Intersections},
+};
+
+/// A flat triangle. The edges and normal are computed once on construction.
+pub struct Triangle {
+    transformation: ShapeTransform,
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+    pub e1: Vector,
+    pub e2: Vector,
+    pub normal: Vector,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Triangle {
+            transformation: ShapeTransform::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).norm(),
+        }
+    }
+
+    /// Möller–Trumbore intersection in object space, returning `t` and the
+    /// barycentric `u` and `v` of the hit.
+    pub(crate) fn local_intersect(&self, ray: &Ray) -> Option<(f64, f64, f64)> {
+        let direction_cross_e2 = ray.direction.cross(self.e2);
+        let determinant = self.e1.dot(direction_cross_e2);
+        if determinant.abs() < EPSILON {
+            // The ray is parallel to the triangle
+            return None;
+        }
+
+        let f = 1. / determinant;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(direction_cross_e2);
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(self.e1);
+        let v = f * ray.direction.dot(origin_cross_e1);
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+
+        Some((f * self.e2.dot(origin_cross_e1), u, v))
+    }
+}
+
+impl Geometry for Triangle {
+    fn shape_transform(&self) -> &ShapeTransform {
+        &self.transformation
+    }
+
+    fn shape_transform_mut(&mut self) -> &mut ShapeTransform {
+        &mut self.transformation
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        bounds.add_point(self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+
+    fn triangle_count(&self) -> usize {
+        1
+    }
+
+    fn intersect(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let ray = self.transformation.local_inverse()? * ray;
+        Ok(match self.local_intersect(&ray) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::empty(),
+        })
+    }
+
+    fn normal(&self, _point: Point) -> Result<Vector, StingrayError> {
+        self.normal_to_world(self.normal)
+    }
+}
+
+impl Mul<Triangle> for Matrix4 {
+    type Output = Triangle;
+
+    fn mul(self, rhs: Triangle) -> Triangle {
+        let mut shape = rhs;
+        shape.set_transform(self);
+        shape
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test::utils::ts, traits::SpecificRound};
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0., 1., 0.),
+            Point::new(-1., 0., 0.),
+            Point::new(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    fn test_triangle_new() {
+        let triangle = triangle();
+
+        assert_eq!(triangle.e1, Vector::new(-1., -1., 0.));
+        assert_eq!(triangle.e2, Vector::new(1., -1., 0.));
+        assert_eq!(triangle.normal, Vector::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_normal_constant() {
+        let triangle = triangle();
+
+        for point in [
+            Point::new(0., 0.5, 0.),
+            Point::new(-0.5, 0.75, 0.),
+            Point::new(0.5, 0.25, 0.),
+        ] {
+            assert_eq!(triangle.normal(point).unwrap(), triangle.normal);
+        }
+    }
+
+    #[test]
+    fn test_ray_parallel() {
+        let ray = Ray::new(Point::new(0., -1., -2.), Vector::new(0., 1., 0.));
+
+        assert!(triangle().intersect(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ray_misses_edges() {
+        let triangle = triangle();
+        for origin in [
+            Point::new(1., 1., -2.),
+            Point::new(-1., 1., -2.),
+            Point::new(0., -1., -2.),
+        ] {
+            let ray = Ray::new(origin, Vector::new(0., 0., 1.));
+            assert!(triangle.intersect(&ray).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_ray_strikes_triangle() {
+        let triangle = triangle();
+        let ray = Ray::new(Point::new(0., 0.5, -2.), Vector::new(0., 0., 1.));
+        let xs = triangle.intersect(&ray).unwrap();
+
+        assert_eq!(ts(&xs), vec![2.]);
+        assert_eq!(xs[0].u.specific_round(5), 0.25);
+        assert_eq!(xs[0].v.specific_round(5), 0.25);
+    }
+
+    #[test]
+    fn test_transformed_triangle() {
+        let triangle = Matrix4::translation(0., 0., 3.) * triangle();
+        let ray = Ray::new(Point::new(0., 0.5, -2.), Vector::new(0., 0., 1.));
+
+        assert_eq!(ts(&triangle.intersect(&ray).unwrap()), vec![5.]);
+    }
+}
@@ -0,0 +1,427 @@
+use std::ops::{Add, Mul, Neg};
+
+use super::{matrix::Matrix, matrix4::Matrix4, vector::Vector};
+use crate::error::StingrayError;
+
+/// A rotation stored as `w + xi + yj + zk`. Unlike Euler angles it has no
+/// gimbal lock, and two rotations can be blended smoothly with `slerp`.
+/// Rotations are unit quaternions; `q` and `-q` turn the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w, x, y, z }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(1., 0., 0., 0.)
+    }
+
+    /// Rotates by `angle` radians about `axis`, which need not be
+    /// normalized but must not be zero.
+    pub fn from_axis_angle(axis: Vector, angle: f64) -> Result<Quaternion, StingrayError> {
+        if axis.mag() == 0. {
+            return Err(StingrayError::InvalidArgument(
+                "rotation axis must not be zero".to_string(),
+            ));
+        }
+        let axis = axis.norm();
+        let (sin, cos) = (angle / 2.).sin_cos();
+        Ok(Quaternion::new(
+            cos,
+            axis.x * sin,
+            axis.y * sin,
+            axis.z * sin,
+        ))
+    }
+
+    /// Rotates about x, then y, then z, in the same order as
+    /// `Transform::rotate_x(x).rotate_y(y).rotate_z(z)`.
+    pub fn from_euler(x: f64, y: f64, z: f64) -> Quaternion {
+        let (sx, cx) = (x / 2.).sin_cos();
+        let (sy, cy) = (y / 2.).sin_cos();
+        let (sz, cz) = (z / 2.).sin_cos();
+        let qx = Quaternion::new(cx, sx, 0., 0.);
+        let qy = Quaternion::new(cy, 0., sy, 0.);
+        let qz = Quaternion::new(cz, 0., 0., sz);
+        qz * qy * qx
+    }
+
+    /// The rotation held in the upper 3x3 of `matrix`. Fails unless the
+    /// matrix is a pure rotation: no scale, shear, mirror or translation.
+    pub fn from_matrix(matrix: &Matrix4) -> Result<Quaternion, StingrayError> {
+        let m = &matrix.0;
+        let not_rotation =
+            || StingrayError::InvalidArgument("matrix is not a pure rotation".to_string());
+        let orthonormal = (0..3).all(|i| {
+            (0..3).all(|j| {
+                let dot: f64 = (0..3).map(|k| m[k][i] * m[k][j]).sum();
+                (dot - if i == j { 1. } else { 0. }).abs() < 1e-6
+            })
+        });
+        let affine = m[3] == [0., 0., 0., 1.] && (0..3).all(|i| m[i][3] == 0.);
+        if !orthonormal || !affine || matrix.determinant() < 0. {
+            return Err(not_rotation());
+        }
+
+        // Take the square root of whichever of w, x, y or z is largest, so
+        // the division below never goes near zero
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let quaternion = if trace > 0. {
+            let s = (trace + 1.).sqrt() * 2.;
+            Quaternion::new(
+                s / 4.,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1. + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.;
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                s / 4.,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (1. + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.;
+            Quaternion::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                s / 4.,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = (1. + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.;
+            Quaternion::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.,
+            )
+        };
+        quaternion.normalize()
+    }
+
+    /// The rotation as a transformation matrix. Quaternions that are not
+    /// unit length are treated as their normalized form. The zero
+    /// quaternion is no rotation at all and gives a matrix of NaNs, so
+    /// check anything built by hand with `normalize` first.
+    pub fn to_matrix(&self) -> Matrix4 {
+        let Quaternion { w, x, y, z } = *self;
+        debug_assert!(
+            self.dot(*self) > 0.,
+            "the zero quaternion has no rotation matrix"
+        );
+        let s = 2. / self.dot(*self);
+        Matrix4([
+            [
+                1. - s * (y * y + z * z),
+                s * (x * y - w * z),
+                s * (x * z + w * y),
+                0.,
+            ],
+            [
+                s * (x * y + w * z),
+                1. - s * (x * x + z * z),
+                s * (y * z - w * x),
+                0.,
+            ],
+            [
+                s * (x * z - w * y),
+                s * (y * z + w * x),
+                1. - s * (x * x + y * y),
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn mag(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn dot(&self, other: Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalize(&self) -> Result<Quaternion, StingrayError> {
+        let mag = self.mag();
+        if mag == 0. {
+            return Err(StingrayError::InvalidArgument(
+                "cannot normalize a zero quaternion".to_string(),
+            ));
+        }
+        Ok(*self * (1. / mag))
+    }
+
+    /// The opposite rotation, for unit quaternions.
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Spherical linear interpolation: turns from `self` at `t = 0` to
+    /// `other` at `t = 1` at a constant angular speed, the short way round.
+    /// Both should be unit quaternions.
+    pub fn slerp(&self, other: Quaternion, t: f64) -> Quaternion {
+        let (mut other, mut cos) = (other, self.dot(other));
+        if cos < 0. {
+            other = -other;
+            cos = -cos;
+        }
+
+        // Nearly equal rotations divide by a vanishing sine, and a straight
+        // line between them is indistinguishable from the arc
+        let blended = if cos > 1. - 1e-9 {
+            *self * (1. - t) + other * t
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            *self * (((1. - t) * angle).sin() / sin) + other * ((t * angle).sin() / sin)
+        };
+        blended * (1. / blended.mag())
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
+
+impl Add for Quaternion {
+    type Output = Quaternion;
+
+    fn add(self, other: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w + other.w,
+            self.x + other.x,
+            self.y + other.y,
+            self.z + other.z,
+        )
+    }
+}
+
+impl Neg for Quaternion {
+    type Output = Quaternion;
+
+    fn neg(self) -> Quaternion {
+        Quaternion::new(-self.w, -self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<f64> for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, scalar: f64) -> Quaternion {
+        Quaternion::new(
+            self.w * scalar,
+            self.x * scalar,
+            self.y * scalar,
+            self.z * scalar,
+        )
+    }
+}
+
+/// Composes rotations: `a * b` turns by `b` first, then by `a`, as with
+/// matrices.
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, other: Quaternion) -> Quaternion {
+        let (a, b) = (self, other);
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+}
+
+/// Rotates the vector, for unit quaternions.
+impl Mul<Vector> for Quaternion {
+    type Output = Vector;
+
+    fn mul(self, vector: Vector) -> Vector {
+        let axis = Vector::new(self.x, self.y, self.z);
+        let twice = axis.cross(vector) * 2.;
+        vector + twice * self.w + axis.cross(twice)
+    }
+}
+
+impl From<Quaternion> for Matrix4 {
+    fn from(quaternion: Quaternion) -> Matrix4 {
+        quaternion.to_matrix()
+    }
+}
+
+impl From<Quaternion> for Matrix {
+    fn from(quaternion: Quaternion) -> Matrix {
+        Matrix::from(quaternion.to_matrix())
+    }
+}
+
+impl TryFrom<Matrix4> for Quaternion {
+    type Error = StingrayError;
+
+    fn try_from(matrix: Matrix4) -> Result<Quaternion, StingrayError> {
+        Quaternion::from_matrix(&matrix)
+    }
+}
+
+impl TryFrom<&Matrix> for Quaternion {
+    type Error = StingrayError;
+
+    fn try_from(matrix: &Matrix) -> Result<Quaternion, StingrayError> {
+        Quaternion::from_matrix(&Matrix4::try_from(matrix)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{
+        geo::{matrix::Rotation, transform::Transform},
+        traits::SpecificRound,
+    };
+
+    fn rounded(q: Quaternion) -> (f64, f64, f64, f64) {
+        (
+            q.w.specific_round(9),
+            q.x.specific_round(9),
+            q.y.specific_round(9),
+            q.z.specific_round(9),
+        )
+    }
+
+    #[test]
+    fn test_axis_angle() {
+        let axis = Vector::new(1., 2., -2.);
+        let quaternion = Quaternion::from_axis_angle(axis, 0.7).unwrap();
+
+        assert_eq!(quaternion.mag().specific_round(9), 1.);
+        assert_eq!(
+            quaternion.to_matrix().round(9),
+            Matrix4::rotation_about(axis, 0.7).round(9)
+        );
+        let turned = Quaternion::from_axis_angle(Vector::new(0., 0., 1.), PI / 2.).unwrap()
+            * Vector::new(1., 0., 0.);
+        assert_eq!(
+            (turned.x.specific_round(9), turned.y.specific_round(9)),
+            (0., 1.)
+        );
+        assert!(matches!(
+            Quaternion::from_axis_angle(Vector::new(0., 0., 0.), 1.),
+            Err(StingrayError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_euler_matches_transform() {
+        let quaternion = Quaternion::from_euler(0.3, -1.1, 2.);
+        let transform = Transform::identity()
+            .rotate_x(0.3)
+            .rotate_y(-1.1)
+            .rotate_z(2.);
+
+        assert_eq!(
+            Matrix4::from(quaternion).round(9),
+            transform.matrix().round(9)
+        );
+        assert_eq!(
+            Quaternion::from_euler(0., PI / 2., 0.).to_matrix().round(9),
+            Matrix4::rotation(PI / 2., Rotation::Y).round(9)
+        );
+    }
+
+    #[test]
+    fn test_matrix_round_trip() {
+        // Half turns have a zero or negative trace and take the other branches
+        let quaternions = [
+            Quaternion::from_euler(0.3, -1.1, 2.),
+            Quaternion::from_axis_angle(Vector::new(1., 0.1, 0.), PI).unwrap(),
+            Quaternion::from_axis_angle(Vector::new(0.1, 1., 0.), PI).unwrap(),
+            Quaternion::from_axis_angle(Vector::new(0., 0.1, 1.), PI).unwrap(),
+        ];
+        for quaternion in quaternions {
+            let back = Quaternion::try_from(quaternion.to_matrix()).unwrap();
+            // Either sign is the same rotation
+            let back = if back.dot(quaternion) < 0. {
+                -back
+            } else {
+                back
+            };
+            assert_eq!(rounded(back), rounded(quaternion));
+        }
+
+        let general = Matrix::from(Quaternion::from_euler(0., 0., PI / 2.));
+        assert_eq!(
+            rounded(Quaternion::try_from(&general).unwrap()),
+            rounded(Quaternion::from_euler(0., 0., PI / 2.))
+        );
+    }
+
+    #[test]
+    fn test_from_matrix_rejects_non_rotations() {
+        let not_rotations = [
+            Matrix4::scaling(2., 1., 1.),
+            Matrix4::scaling(-1., 1., 1.),
+            Matrix4::translation(1., 0., 0.),
+        ];
+        for matrix in not_rotations {
+            assert!(matches!(
+                Quaternion::from_matrix(&matrix),
+                Err(StingrayError::InvalidArgument(_))
+            ));
+        }
+        assert!(matches!(
+            Quaternion::try_from(&Matrix::new(3, 3)),
+            Err(StingrayError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_normalize_and_conjugate() {
+        let quaternion = Quaternion::new(0., 3., 0., 4.).normalize().unwrap();
+        assert_eq!(rounded(quaternion), (0., 0.6, 0., 0.8));
+        assert_eq!(
+            rounded(quaternion * quaternion.conjugate()),
+            rounded(Quaternion::identity())
+        );
+        assert!(Quaternion::new(0., 0., 0., 0.).normalize().is_err());
+        assert_eq!(
+            Quaternion::new(2., 0., 0., 0.).to_matrix(),
+            Matrix4::identity()
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the zero quaternion has no rotation matrix")]
+    fn test_zero_quaternion_matrix() {
+        Quaternion::new(0., 0., 0., 0.).to_matrix();
+    }
+
+    #[test]
+    fn test_slerp() {
+        let start = Quaternion::identity();
+        let end = Quaternion::from_axis_angle(Vector::new(0., 1., 0.), PI / 2.).unwrap();
+        let quarter = Quaternion::from_axis_angle(Vector::new(0., 1., 0.), PI / 8.).unwrap();
+
+        assert_eq!(rounded(start.slerp(end, 0.)), rounded(start));
+        assert_eq!(rounded(start.slerp(end, 1.)), rounded(end));
+        assert_eq!(rounded(start.slerp(end, 0.25)), rounded(quarter));
+        // -end is the same rotation, and still takes the short way round
+        assert_eq!(rounded(start.slerp(-end, 0.25)), rounded(quarter));
+        assert_eq!(rounded(end.slerp(end, 0.5)), rounded(end));
+    }
+}
@@ -1,12 +1,10 @@
 #[cfg(test)]
-pub mod test {
+mod tests {
     use crate::canvas::Canvas;
     use crate::color::Color;
     use crate::geo::matrix::{Matrix, Rotation};
     use crate::geo::point::Point;
 
-    use super::*;
-
     #[test]
     fn draw_circle() {
         let mut canvas = Canvas::new(100, 100);
@@ -19,11 +17,12 @@ pub mod test {
             let point = rot * point;
             // Translate after rotation
             let point = point + Point::new(50., 50., 0.);
-            let color = Color::new(255., 0., 0.);
+            let color = Color::new(1., 0., 0.);
             canvas.draw_point(point, color);
         }
 
-        canvas.save_ppm("circle.ppm");
+        let path = std::env::temp_dir().join("stingray-circle.ppm");
+        canvas.save_ppm(&path.to_string_lossy()).unwrap();
     }
 
     #[test]
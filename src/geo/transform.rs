@@ -0,0 +1,309 @@
+use std::ops::Mul;
+
+use super::{matrix::Rotation, matrix4::Matrix4, point::Point, ray::Ray, vector::Vector};
+use crate::error::StingrayError;
+
+/// Builds a transformation one step at a time, applied in the order the
+/// steps are written. `Transform::identity().rotate_x(a).scale(2., 2., 2.)`
+/// rotates first and then scales, which as matrices is `scaling * rotation`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transform {
+    matrix: Matrix4,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+
+    /// Applies `step` after everything so far.
+    pub fn then(self, step: impl Into<Matrix4>) -> Transform {
+        Transform {
+            matrix: step.into() * self.matrix,
+        }
+    }
+
+    pub fn translate(self, x: f64, y: f64, z: f64) -> Transform {
+        self.then(Matrix4::translation(x, y, z))
+    }
+
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Transform {
+        self.then(Matrix4::scaling(x, y, z))
+    }
+
+    pub fn rotate_x(self, angle: f64) -> Transform {
+        self.then(Matrix4::rotation(angle, Rotation::X))
+    }
+
+    pub fn rotate_y(self, angle: f64) -> Transform {
+        self.then(Matrix4::rotation(angle, Rotation::Y))
+    }
+
+    pub fn rotate_z(self, angle: f64) -> Transform {
+        self.then(Matrix4::rotation(angle, Rotation::Z))
+    }
+
+    /// Rotates by `angle` radians about `axis` through the origin.
+    pub fn rotate(self, axis: Vector, angle: f64) -> Transform {
+        self.then(Matrix4::rotation_about(axis, angle))
+    }
+
+    pub fn shear(self, x_y: f64, x_z: f64, y_x: f64, y_z: f64, z_x: f64, z_y: f64) -> Transform {
+        self.then(Matrix4::shearing(x_y, x_z, y_x, y_z, z_x, z_y))
+    }
+
+    /// Splits the transformation into scale, shear, rotation and
+    /// translation, applied in that order. Fails for singular matrices
+    /// and projective ones whose bottom row is not `[0, 0, 0, 1]`.
+    pub fn decompose(&self) -> Result<Decomposition, StingrayError> {
+        let m = &self.matrix.0;
+        if m[3] != [0., 0., 0., 1.] {
+            return Err(StingrayError::Unsupported(
+                "projective transformations cannot be decomposed".to_string(),
+            ));
+        }
+
+        // The upper 3x3 is rotation * shear * scale. Gram-Schmidt on its
+        // columns gives the rotation, and the upper triangle left over
+        // holds the scale on its diagonal and the shear above it.
+        let column = |j: usize| Vector::new(m[0][j], m[1][j], m[2][j]);
+        let (c0, c1, c2) = (column(0), column(1), column(2));
+        // The matrix is singular when a column is all but lost to the ones
+        // before it. Measuring against the column's own length keeps tiny
+        // but valid scales from counting as zero.
+        let remainder = |u: Vector, c: Vector| {
+            let length = u.mag();
+            if length <= c.mag() * 1e-9 {
+                Err(StingrayError::Singular)
+            } else {
+                Ok(length)
+            }
+        };
+
+        let mut sx = remainder(c0, c0)?;
+        let mut q0 = c0 / sx;
+        let r01 = q0.dot(c1);
+        let u1 = c1 - q0 * r01;
+        let sy = remainder(u1, c1)?;
+        let q1 = u1 / sy;
+        let r02 = q0.dot(c2);
+        let r12 = q1.dot(c2);
+        let u2 = c2 - q0 * r02 - q1 * r12;
+        let sz = remainder(u2, c2)?;
+        let q2 = u2 / sz;
+
+        // A mirror image leaves the columns left handed; fold the
+        // reflection into the x scale so the rest is a proper rotation
+        let mut r01 = r01;
+        let mut r02 = r02;
+        if q0.cross(q1).dot(q2) < 0. {
+            sx = -sx;
+            q0 = -q0;
+            r01 = -r01;
+            r02 = -r02;
+        }
+
+        Ok(Decomposition {
+            translation: Vector::new(m[0][3], m[1][3], m[2][3]),
+            rotation: euler_angles([q0, q1, q2]),
+            scale: Vector::new(sx, sy, sz),
+            shear: (r01 / sy, r02 / sz, r12 / sz),
+        })
+    }
+}
+
+/// The x, y and z angles of the rotation with these columns, for
+/// rotating about x, then y, then z.
+fn euler_angles([c0, c1, c2]: [Vector; 3]) -> (f64, f64, f64) {
+    let y = (-c0.z).clamp(-1., 1.).asin();
+    if y.cos() > 1e-9 {
+        (c1.z.atan2(c2.z), y, c0.y.atan2(c0.x))
+    } else {
+        // Gimbal lock: x and z turn about the same axis, so put it all in x
+        ((-c2.y).atan2(c1.y), y, 0.)
+    }
+}
+
+/// The parts of an affine transformation, which `to_transform` puts back
+/// together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decomposition {
+    pub translation: Vector,
+    /// Radians about x, then y, then z.
+    pub rotation: (f64, f64, f64),
+    /// Negative on x for mirror images.
+    pub scale: Vector,
+    /// The `x_y`, `x_z` and `y_z` factors of [`Matrix4::shearing`].
+    pub shear: (f64, f64, f64),
+}
+
+impl Decomposition {
+    pub fn to_transform(&self) -> Transform {
+        let (rx, ry, rz) = self.rotation;
+        let (x_y, x_z, y_z) = self.shear;
+        Transform::identity()
+            .scale(self.scale.x, self.scale.y, self.scale.z)
+            .shear(x_y, x_z, 0., y_z, 0., 0.)
+            .rotate_x(rx)
+            .rotate_y(ry)
+            .rotate_z(rz)
+            .translate(self.translation.x, self.translation.y, self.translation.z)
+    }
+}
+
+impl From<Transform> for Matrix4 {
+    fn from(transform: Transform) -> Matrix4 {
+        transform.matrix
+    }
+}
+
+impl From<Matrix4> for Transform {
+    fn from(matrix: Matrix4) -> Transform {
+        Transform { matrix }
+    }
+}
+
+impl Mul<Point> for Transform {
+    type Output = Point;
+
+    fn mul(self, point: Point) -> Point {
+        self.matrix * point
+    }
+}
+
+impl Mul<Vector> for Transform {
+    type Output = Vector;
+
+    fn mul(self, vector: Vector) -> Vector {
+        self.matrix * vector
+    }
+}
+
+impl Mul<Ray> for Transform {
+    type Output = Ray;
+
+    fn mul(self, ray: Ray) -> Ray {
+        self.matrix * ray
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn assert_same(left: Matrix4, right: Matrix4) {
+        assert_eq!(left.round(9), right.round(9));
+    }
+
+    #[test]
+    fn test_reading_order() {
+        let transform = Transform::identity()
+            .rotate_x(PI / 2.)
+            .scale(5., 5., 5.)
+            .translate(10., 5., 7.);
+
+        assert_same(
+            transform.matrix(),
+            Matrix4::translation(10., 5., 7.)
+                * Matrix4::scaling(5., 5., 5.)
+                * Matrix4::rotation(PI / 2., Rotation::X),
+        );
+        let point = transform * Point::new(1., 0., 1.);
+        assert_eq!((point.x, point.y.round(), point.z.round()), (15., 0., 7.));
+    }
+
+    #[test]
+    fn test_then_and_conversions() {
+        let rotation = Transform::identity().rotate(Vector::new(0., 0., 3.), PI / 2.);
+        let transform = rotation.then(Matrix4::translation(1., 0., 0.));
+
+        assert_same(rotation.matrix(), Matrix4::rotation(PI / 2., Rotation::Z));
+        assert_eq!(
+            Matrix4::from(transform),
+            Matrix4::translation(1., 0., 0.) * rotation.matrix()
+        );
+        assert_eq!(Transform::from(Matrix4::identity()), Transform::default());
+    }
+
+    #[test]
+    fn test_decompose_round_trip() {
+        let transform = Transform::identity()
+            .scale(2., 0.5, 3.)
+            .shear(0.25, -1., 0., 0.5, 0., 0.)
+            .rotate_x(0.3)
+            .rotate_y(-1.1)
+            .rotate_z(2.)
+            .translate(4., -5., 6.);
+        let parts = transform.decompose().unwrap();
+        let rounded = |(a, b, c): (f64, f64, f64)| {
+            (
+                a.specific_round(9),
+                b.specific_round(9),
+                c.specific_round(9),
+            )
+        };
+
+        assert_eq!(parts.translation, Vector::new(4., -5., 6.));
+        assert_eq!(
+            rounded((parts.scale.x, parts.scale.y, parts.scale.z)),
+            (2., 0.5, 3.)
+        );
+        assert_eq!(rounded(parts.rotation), (0.3, -1.1, 2.));
+        assert_eq!(rounded(parts.shear), (0.25, -1., 0.5));
+        assert_same(parts.to_transform().matrix(), transform.matrix());
+    }
+
+    #[test]
+    fn test_decompose_mirror_and_gimbal_lock() {
+        let mirror = Transform::identity().scale(-1., 2., 2.).rotate_y(0.5);
+        let parts = mirror.decompose().unwrap();
+        assert_eq!(parts.scale.x.round(), -1.);
+        assert_same(parts.to_transform().matrix(), mirror.matrix());
+
+        let locked = Transform::identity()
+            .rotate_x(0.4)
+            .rotate_y(PI / 2.)
+            .rotate_z(0.3);
+        let parts = locked.decompose().unwrap();
+        assert_eq!(parts.rotation.2, 0.);
+        assert_same(parts.to_transform().matrix(), locked.matrix());
+    }
+
+    #[test]
+    fn test_decompose_tiny_scale() {
+        // The determinant is 1e-12, yet the matrix inverts fine
+        let tiny = Transform::identity().scale(1e-4, 1e-4, 1e-4).rotate_z(0.5);
+        let parts = tiny.decompose().unwrap();
+
+        assert!(tiny.matrix().inverse().is_ok());
+        assert_eq!(parts.scale.x.specific_round(12), 1e-4);
+        assert_eq!(parts.rotation.2.specific_round(9), 0.5);
+    }
+
+    #[test]
+    fn test_decompose_errors() {
+        let flat = Transform::identity().scale(1., 0., 1.);
+        assert!(matches!(flat.decompose(), Err(StingrayError::Singular)));
+        let rotated_flat = Transform::identity().scale(1., 1., 0.).rotate_y(0.7);
+        assert!(matches!(
+            rotated_flat.decompose(),
+            Err(StingrayError::Singular)
+        ));
+
+        let mut projective = Matrix4::identity();
+        projective[3][2] = 1.;
+        assert!(matches!(
+            Transform::from(projective).decompose(),
+            Err(StingrayError::Unsupported(_))
+        ));
+    }
+}
@@ -23,7 +23,7 @@ impl Vector {
     }
 
     pub fn norm(&self) -> Self {
-        self.clone() / self.mag()
+        *self / self.mag()
     }
 
     pub fn cross(&self, other: Self) -> Self {
@@ -36,7 +36,7 @@ impl Vector {
 
     pub fn reflect(&self, n: Vector) -> Self {
         let dot = self.dot(n);
-        self.clone() - n.clone() * (2. * dot)
+        *self - n * (2. * dot)
     }
 }
 
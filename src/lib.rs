@@ -0,0 +1,11 @@
+pub mod camera;
+pub mod canvas;
+pub mod color;
+pub mod error;
+pub mod geo;
+pub mod lighting;
+pub mod obj;
+pub mod scene;
+pub mod test;
+pub mod traits;
+pub mod world;
@@ -1,11 +1,12 @@
 use crate::{
-    assert_fractional,
     color::Color,
-    geo::{objects::sphere::Sphere, point::Point},
+    error::StingrayError,
+    geo::{point::Point, vector::Vector},
 };
 
 pub mod texture;
 
+#[derive(Debug, Clone, Copy)]
 pub struct PointLight {
     pub position: Point,
     pub color: Color,
@@ -19,25 +20,231 @@ impl PointLight {
 
 #[derive(Debug, Clone, Copy)]
 pub struct Material {
-    color: Color,
-    ambient: f32,
-    diffuse: f32,
-    specular: f32,
-    shininess: f32,
+    pub color: Color,
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
 }
 
 impl Material {
-    pub fn new(color: Color, ambient: f32, diffuse: f32, specular: f32, shininess: f32) -> Self {
-        assert_fractional!(ambient);
-        assert_fractional!(diffuse);
-        assert_fractional!(specular);
-        assert_fractional!(shininess);
-        Self {
+    /// Panics where `try_new` would return an error.
+    pub fn new(color: Color, ambient: f64, diffuse: f64, specular: f64, shininess: f64) -> Self {
+        Self::try_new(color, ambient, diffuse, specular, shininess)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// A material whose ambient, diffuse and specular reflection are each
+    /// between 0 and 1, with a positive shininess.
+    pub fn try_new(
+        color: Color,
+        ambient: f64,
+        diffuse: f64,
+        specular: f64,
+        shininess: f64,
+    ) -> Result<Self, StingrayError> {
+        for (name, value) in [
+            ("ambient", ambient),
+            ("diffuse", diffuse),
+            ("specular", specular),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(StingrayError::InvalidMaterial(format!(
+                    "{name}: must be between 0 and 1, found {value}"
+                )));
+            }
+        }
+        if shininess <= 0. || shininess.is_nan() {
+            return Err(StingrayError::InvalidMaterial(format!(
+                "shininess: must be greater than 0, found {shininess}"
+            )));
+        }
+        Ok(Self {
             color,
             ambient,
             diffuse,
             specular,
             shininess,
+        })
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material::new(Color::white(), 0.1, 0.9, 0.9, 200.)
+    }
+}
+
+/// Phong shading of `point` as seen from `eye`: ambient, Lambertian diffuse and
+/// specular highlight contributions of a single light. A point in shadow only
+/// receives the ambient term.
+pub fn lighting(
+    material: &Material,
+    light: &PointLight,
+    point: Point,
+    eye: Vector,
+    normal: Vector,
+    in_shadow: bool,
+) -> Color {
+    let effective_color = material.color * light.color;
+    let ambient = effective_color * material.ambient;
+    if in_shadow {
+        return ambient;
+    }
+
+    let light_direction = (light.position - point).norm();
+    let light_dot_normal = light_direction.dot(normal);
+    if light_dot_normal < 0. {
+        // The light is on the other side of the surface
+        return ambient;
+    }
+    let diffuse = effective_color * material.diffuse * light_dot_normal;
+
+    let reflect_direction = (-light_direction).reflect(normal);
+    let reflect_dot_eye = reflect_direction.dot(eye);
+    let specular = if reflect_dot_eye <= 0. {
+        Color::black()
+    } else {
+        light.color * material.specular * reflect_dot_eye.powf(material.shininess)
+    };
+
+    ambient + diffuse + specular
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+    use crate::traits::SpecificRound;
+
+    fn setup() -> (Material, Point) {
+        (Material::default(), Point::new(0., 0., 0.))
+    }
+
+    fn rounded(color: Color) -> (f64, f64, f64) {
+        (
+            color.r.specific_round(4),
+            color.g.specific_round(4),
+            color.b.specific_round(4),
+        )
+    }
+
+    #[test]
+    fn test_try_new() {
+        let material = Material::try_new(Color::white(), 0., 1., 0., 10.).unwrap();
+        assert_eq!(material.diffuse, 1.);
+
+        for (ambient, diffuse, specular, shininess, message) in [
+            (
+                1.5,
+                0.9,
+                0.9,
+                200.,
+                "ambient: must be between 0 and 1, found 1.5",
+            ),
+            (
+                0.1,
+                -0.1,
+                0.9,
+                200.,
+                "diffuse: must be between 0 and 1, found -0.1",
+            ),
+            (
+                0.1,
+                0.9,
+                0.9,
+                0.,
+                "shininess: must be greater than 0, found 0",
+            ),
+        ] {
+            match Material::try_new(Color::white(), ambient, diffuse, specular, shininess) {
+                Err(StingrayError::InvalidMaterial(found)) => assert_eq!(found, message),
+                other => panic!("expected an invalid material, found {other:?}"),
+            }
         }
     }
+
+    #[test]
+    #[should_panic(expected = "specular: must be between 0 and 1")]
+    fn test_new_panics_on_invalid_material() {
+        Material::new(Color::white(), 0.1, 0.9, 2., 200.);
+    }
+
+    #[test]
+    fn test_lighting_eye_between_light_and_surface() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., 0., -1.);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+
+        let result = lighting(&material, &light, point, eye, normal, false);
+        assert_eq!(rounded(result), (1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn test_lighting_eye_offset_45() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+
+        let result = lighting(&material, &light, point, eye, normal, false);
+        assert_eq!(rounded(result), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_lighting_light_offset_45() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., 0., -1.);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 10., -10.), Color::white());
+
+        let result = lighting(&material, &light, point, eye, normal, false);
+        assert_eq!(rounded(result), (0.7364, 0.7364, 0.7364));
+    }
+
+    #[test]
+    fn test_lighting_eye_in_reflection_path() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., -FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 10., -10.), Color::white());
+
+        let result = lighting(&material, &light, point, eye, normal, false);
+        assert_eq!(rounded(result), (1.6364, 1.6364, 1.6364));
+    }
+
+    #[test]
+    fn test_lighting_light_behind_surface() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., 0., -1.);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 0., 10.), Color::white());
+
+        let result = lighting(&material, &light, point, eye, normal, false);
+        assert_eq!(rounded(result), (0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn test_lighting_in_shadow() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., 0., -1.);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+
+        let result = lighting(&material, &light, point, eye, normal, true);
+        assert_eq!(rounded(result), (0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn test_lighting_respects_light_color() {
+        let (material, point) = setup();
+        let eye = Vector::new(0., 0., -1.);
+        let normal = Vector::new(0., 0., -1.);
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::new(1., 0.5, 0.));
+
+        let result = lighting(&material, &light, point, eye, normal, false);
+        assert_eq!(rounded(result), (1.9, 0.95, 0.));
+    }
 }
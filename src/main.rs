@@ -1,10 +1,382 @@
-mod canvas;
-mod color;
-mod geo;
-mod lighting;
-mod test;
-mod traits;
-
-fn main() {
-    println!("Hello, world!");
+use std::{env, fs, io, path::Path, process::ExitCode};
+
+use stingray::{
+    camera::Camera,
+    canvas::{Canvas, ExrOptions, PngDepth, ToneOperator},
+    error::StingrayError,
+    scene::Scene,
+};
+
+const USAGE: &str = "\
+usage: stingray render <scene.yml> [-o <out.png|ppm|hdr|exr>] [--width <px>] [--height <px>] [--samples <n>]
+                       [--exposure <stops>] [--tonemap <clamp|reinhard|aces|uncharted2>]
+       stingray info <scene.yml>
+
+Exposure and tone mapping apply to .png and .ppm output; .hdr and .exr
+keep the unclamped linear render.";
+
+/// Groups with more children than this are split into a bounding volume
+/// hierarchy before rendering.
+const BVH_THRESHOLD: usize = 8;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Render(RenderOptions),
+    Info { scene: String },
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+struct RenderOptions {
+    scene: String,
+    output: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    samples: u32,
+    exposure: f64,
+    tone: ToneOperator,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(|command| match command {
+        Command::Render(options) => render(&options),
+        Command::Info { scene } => info(&scene),
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("stingray: {}", err.report());
+            // Usage mistakes exit with 2, like other command line tools
+            match err {
+                StingrayError::InvalidArgument(_) => ExitCode::from(2),
+                _ => ExitCode::FAILURE,
+            }
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, StingrayError> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => {
+            return Err(StingrayError::InvalidArgument(format!(
+                "missing command\n{USAGE}"
+            )))
+        }
+    };
+
+    match command {
+        "render" => parse_render(rest).map(Command::Render),
+        "info" => match rest {
+            [scene] => Ok(Command::Info {
+                scene: scene.clone(),
+            }),
+            [] => Err(StingrayError::InvalidArgument(
+                "info: missing scene file".to_string(),
+            )),
+            _ => Err(StingrayError::InvalidArgument(format!(
+                "info: unexpected argument '{}'",
+                rest[1]
+            ))),
+        },
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        other => Err(StingrayError::InvalidArgument(format!(
+            "unknown command '{other}'\n{USAGE}"
+        ))),
+    }
+}
+
+fn parse_render(args: &[String]) -> Result<RenderOptions, StingrayError> {
+    let mut scene = None;
+    let mut output = None;
+    let mut width = None;
+    let mut height = None;
+    let mut samples = 1;
+    let mut exposure = 0.;
+    let mut tone = ToneOperator::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next().cloned().ok_or_else(|| {
+                StingrayError::InvalidArgument(format!("render: {flag} needs a value"))
+            })
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(value(arg)?),
+            "--width" => width = Some(parse_positive(arg, &value(arg)?)?),
+            "--height" => height = Some(parse_positive(arg, &value(arg)?)?),
+            "--samples" => samples = parse_positive(arg, &value(arg)?)?,
+            "--exposure" => {
+                let stops = value(arg)?;
+                exposure = match stops.parse::<f64>() {
+                    Ok(stops) if stops.is_finite() => stops,
+                    _ => {
+                        return Err(StingrayError::InvalidArgument(format!(
+                            "{arg}: expected a number of stops, got '{stops}'"
+                        )))
+                    }
+                };
+            }
+            "--tonemap" => {
+                tone = value(arg)?
+                    .parse()
+                    .map_err(|e: StingrayError| StingrayError::InvalidArgument(e.to_string()))?
+            }
+            flag if flag.starts_with('-') => {
+                return Err(StingrayError::InvalidArgument(format!(
+                    "render: unknown option '{flag}'"
+                )));
+            }
+            path if scene.is_none() => scene = Some(path.to_string()),
+            path => {
+                return Err(StingrayError::InvalidArgument(format!(
+                    "render: unexpected argument '{path}'"
+                )))
+            }
+        }
+    }
+
+    let scene = scene.ok_or(StingrayError::InvalidArgument(
+        "render: missing scene file".to_string(),
+    ))?;
+    let output = output.unwrap_or_else(|| {
+        Path::new(&scene)
+            .with_extension("png")
+            .to_string_lossy()
+            .into_owned()
+    });
+    Ok(RenderOptions {
+        scene,
+        output,
+        width,
+        height,
+        samples,
+        exposure,
+        tone,
+    })
+}
+
+fn parse_positive(flag: &str, value: &str) -> Result<u32, StingrayError> {
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(StingrayError::InvalidArgument(format!(
+            "{flag}: expected a positive integer, got '{value}'"
+        ))),
+    }
+}
+
+fn render(options: &RenderOptions) -> Result<(), StingrayError> {
+    let mut scene = Scene::load(&options.scene)?;
+    warn(&scene);
+    scene.world.divide(BVH_THRESHOLD);
+
+    // Resizing keeps the scene's field of view and viewpoint
+    let camera = &scene.camera;
+    let mut camera = Camera::new(
+        options.width.unwrap_or(camera.hsize),
+        options.height.unwrap_or(camera.vsize),
+        camera.field_of_view,
+    );
+    camera.set_transform(scene.camera.transform());
+
+    // Check the extension up front rather than after a long render
+    let format = ImageFormat::from_path(&options.output)?;
+    let mut canvas = camera.render_samples(&scene.world, options.samples)?;
+    if format.is_display_referred() {
+        canvas = canvas.tone_map(options.tone, options.exposure);
+    }
+    format
+        .save(&canvas, &options.output)
+        .map_err(|source| StingrayError::Io {
+            path: options.output.clone(),
+            source,
+        })?;
+    println!(
+        "wrote {} ({}x{}, {} sample{} per pixel)",
+        options.output,
+        camera.hsize,
+        camera.vsize,
+        options.samples,
+        if options.samples == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
+
+/// Output encodings the CLI can write, chosen by file extension.
+#[derive(Debug, PartialEq)]
+enum ImageFormat {
+    Ppm,
+    Png,
+    Hdr,
+    Exr,
+}
+
+impl ImageFormat {
+    fn from_path(path: &str) -> Result<ImageFormat, StingrayError> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("ppm") => Ok(ImageFormat::Ppm),
+            Some("png") => Ok(ImageFormat::Png),
+            Some("hdr") => Ok(ImageFormat::Hdr),
+            Some("exr") => Ok(ImageFormat::Exr),
+            _ => Err(StingrayError::InvalidArgument(format!(
+                "{path}: unknown image format, expected .png, .ppm, .hdr or .exr"
+            ))),
+        }
+    }
+
+    /// Whether the format holds 0–1 display values rather than radiance.
+    fn is_display_referred(&self) -> bool {
+        matches!(self, ImageFormat::Ppm | ImageFormat::Png)
+    }
+
+    fn save(&self, canvas: &Canvas, path: &str) -> io::Result<()> {
+        match self {
+            // PPM has no colour space tag, so viewers assume sRGB
+            ImageFormat::Ppm => fs::write(path, canvas.to_srgb().to_ppm_binary()),
+            ImageFormat::Png => canvas.save_png_with_depth(path, PngDepth::Eight),
+            ImageFormat::Hdr => canvas.save_hdr(path),
+            ImageFormat::Exr => canvas.save_exr(path, ExrOptions::default()),
+        }
+    }
+}
+
+fn warn(scene: &Scene) {
+    for warning in scene.warnings.iter() {
+        eprintln!("stingray: warning: {warning}");
+    }
+}
+
+fn info(path: &str) -> Result<(), StingrayError> {
+    let scene = Scene::load(path)?;
+    warn(&scene);
+    let world = &scene.world;
+    let bounds = world.bounds();
+
+    println!("objects:   {}", world.objects().len());
+    println!("lights:    {}", world.lights.len());
+    println!("triangles: {}", world.triangle_count());
+    if bounds.is_empty() {
+        println!("bounds:    empty");
+    } else {
+        println!(
+            "bounds:    ({}, {}, {}) to ({}, {}, {})",
+            bounds.min.x, bounds.min.y, bounds.min.z, bounds.max.x, bounds.max.y, bounds.max.z
+        );
+    }
+    println!(
+        "camera:    {}x{}, field of view {:.4} rad",
+        scene.camera.hsize, scene.camera.vsize, scene.camera.field_of_view
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_render() {
+        let command = parse_args(&args(
+            "render scene.yml -o out.ppm --width 800 --height 600 --samples 4",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            command,
+            Command::Render(RenderOptions {
+                scene: "scene.yml".to_string(),
+                output: "out.ppm".to_string(),
+                width: Some(800),
+                height: Some(600),
+                samples: 4,
+                exposure: 0.,
+                tone: ToneOperator::Clamp,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_render_defaults() {
+        let command = parse_args(&args("render scenes/cover.yml")).unwrap();
+
+        assert_eq!(
+            command,
+            Command::Render(RenderOptions {
+                scene: "scenes/cover.yml".to_string(),
+                output: "scenes/cover.png".to_string(),
+                width: None,
+                height: None,
+                samples: 1,
+                exposure: 0.,
+                tone: ToneOperator::Clamp,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_render_tone_mapping() {
+        let command = parse_args(&args("render scene.yml --exposure -1.5 --tonemap aces")).unwrap();
+
+        match command {
+            Command::Render(options) => {
+                assert_eq!(options.exposure, -1.5);
+                assert_eq!(options.tone, ToneOperator::Aces);
+            }
+            other => panic!("expected a render command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_info() {
+        assert_eq!(
+            parse_args(&args("info scene.yml")).unwrap(),
+            Command::Info {
+                scene: "scene.yml".to_string()
+            }
+        );
+        assert_eq!(parse_args(&args("--help")).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_args(&[]).is_err());
+        assert!(parse_args(&args("draw scene.yml")).is_err());
+        assert!(parse_args(&args("render")).is_err());
+        assert!(parse_args(&args("render scene.yml --width")).is_err());
+        assert!(parse_args(&args("render scene.yml --samples 0")).is_err());
+        assert!(parse_args(&args("render scene.yml --height tall")).is_err());
+        assert!(parse_args(&args("render scene.yml --fast")).is_err());
+        assert!(parse_args(&args("render scene.yml --exposure bright")).is_err());
+        assert!(parse_args(&args("render scene.yml --tonemap filmic")).is_err());
+        assert!(parse_args(&args("render a.yml b.yml")).is_err());
+        assert!(parse_args(&args("info a.yml b.yml")).is_err());
+    }
+
+    #[test]
+    fn test_image_format_from_extension() {
+        assert_eq!(ImageFormat::from_path("out.ppm").unwrap(), ImageFormat::Ppm);
+        assert_eq!(
+            ImageFormat::from_path("renders/OUT.PNG").unwrap(),
+            ImageFormat::Png
+        );
+        assert_eq!(ImageFormat::from_path("out.hdr").unwrap(), ImageFormat::Hdr);
+        assert_eq!(ImageFormat::from_path("out.exr").unwrap(), ImageFormat::Exr);
+        assert!(ImageFormat::from_path("out.jpg").is_err());
+        assert!(ImageFormat::from_path("out").is_err());
+    }
 }
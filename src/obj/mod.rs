@@ -0,0 +1,406 @@
+use std::{fs, ops::RangeInclusive};
+
+use crate::{
+    error::StingrayError,
+    geo::{
+        objects::{group::Group, smooth_triangle::SmoothTriangle, triangle::Triangle},
+        point::Point,
+        vector::Vector,
+    },
+};
+
+/// A triangle from a face record, with its vertex normals if every vertex of
+/// the face named one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Face {
+    pub vertices: [Point; 3],
+    pub normals: Option<[Vector; 3]>,
+}
+
+#[derive(Debug, Default)]
+pub struct ObjGroup {
+    pub name: String,
+    pub faces: Vec<Face>,
+}
+
+/// The contents of a Wavefront OBJ file. Faces that appear before any `g` or
+/// `o` record belong to the default group.
+#[derive(Debug, Default)]
+pub struct ObjFile {
+    pub vertices: Vec<Point>,
+    pub normals: Vec<Vector>,
+    pub texture_coordinates: Vec<(f64, f64)>,
+    pub default_group: Vec<Face>,
+    pub groups: Vec<ObjGroup>,
+    /// Line numbers and contents of the records the parser does not understand.
+    pub ignored: Vec<(usize, String)>,
+}
+
+impl ObjFile {
+    pub fn load(path: &str) -> Result<ObjFile, StingrayError> {
+        let input = fs::read_to_string(path).map_err(|source| StingrayError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        parse_obj(&input)
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.default_group.len() + self.groups.iter().map(|g| g.faces.len()).sum::<usize>()
+    }
+
+    /// Builds the shapes for every face: a subgroup per named group, plus the
+    /// faces of the default group directly in the returned group.
+    pub fn to_group(&self) -> Group {
+        let mut group = Group::new();
+        add_faces(&mut group, &self.default_group);
+        for named in self.groups.iter() {
+            let mut subgroup = Group::new();
+            add_faces(&mut subgroup, &named.faces);
+            group.add_child(subgroup);
+        }
+        group
+    }
+
+    fn group_mut(&mut self, name: Option<&str>) -> &mut Vec<Face> {
+        let Some(name) = name else {
+            return &mut self.default_group;
+        };
+        let index = match self.groups.iter().position(|g| g.name == name) {
+            Some(index) => index,
+            None => {
+                self.groups.push(ObjGroup {
+                    name: name.to_string(),
+                    faces: vec![],
+                });
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[index].faces
+    }
+}
+
+fn add_faces(group: &mut Group, faces: &[Face]) {
+    for face in faces {
+        let [p1, p2, p3] = face.vertices;
+        match face.normals {
+            Some([n1, n2, n3]) => group.add_child(SmoothTriangle::new(p1, p2, p3, n1, n2, n3)),
+            None => group.add_child(Triangle::new(p1, p2, p3)),
+        }
+    }
+}
+
+/// Parses OBJ source. Unknown records are collected in `ignored`; malformed
+/// records fail with the offending line number.
+pub fn parse_obj(input: &str) -> Result<ObjFile, StingrayError> {
+    let mut obj = ObjFile::default();
+    let mut current_group: Option<String> = None;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |e: StingrayError| e.context(format!("line {line_number}"));
+
+        let content = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = content.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                // An optional w, or r g b vertex colours, may follow
+                let [x, y, z] = parse_floats::<3>(&arguments, 3..=7).map_err(error)?;
+                obj.vertices.push(Point::new(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(&arguments, 3..=3).map_err(error)?;
+                obj.normals.push(Vector::new(x, y, z));
+            }
+            "vt" => {
+                // v defaults to 0, and the optional w coordinate is unused
+                let [u, v] = parse_floats::<2>(&arguments, 1..=3).map_err(error)?;
+                obj.texture_coordinates.push((u, v));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error(StingrayError::Parse(format!(
+                        "face needs at least 3 vertices, found {}",
+                        arguments.len()
+                    ))));
+                }
+                let corners = arguments
+                    .iter()
+                    .map(|argument| parse_face_vertex(&obj, argument))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+
+                // Fan triangulation around the first vertex
+                let faces: Vec<Face> = (1..corners.len() - 1)
+                    .map(|i| {
+                        let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                        let normals = match (a.1, b.1, c.1) {
+                            (Some(n1), Some(n2), Some(n3)) => Some([n1, n2, n3]),
+                            _ => None,
+                        };
+                        Face {
+                            vertices: [a.0, b.0, c.0],
+                            normals,
+                        }
+                    })
+                    .collect();
+                obj.group_mut(current_group.as_deref()).extend(faces);
+            }
+            "g" | "o" => {
+                if arguments.is_empty() {
+                    return Err(error(StingrayError::Parse(format!(
+                        "'{keyword}' record without a name"
+                    ))));
+                }
+                let name = arguments.join(" ");
+                obj.group_mut(Some(&name));
+                current_group = Some(name);
+            }
+            _ => obj.ignored.push((line_number, line.to_string())),
+        }
+    }
+
+    Ok(obj)
+}
+
+/// Parses `count` numbers and returns the first `N`, with any missing
+/// ones zero. Numbers past the first `N` are checked but dropped.
+fn parse_floats<const N: usize>(
+    arguments: &[&str],
+    count: RangeInclusive<usize>,
+) -> Result<[f64; N], StingrayError> {
+    if !count.contains(&arguments.len()) {
+        let expected = match (count.start(), count.end()) {
+            (min, max) if min == max => min.to_string(),
+            (min, max) => format!("{min} to {max}"),
+        };
+        return Err(StingrayError::Parse(format!(
+            "expected {expected} numbers, found {}",
+            arguments.len()
+        )));
+    }
+    let mut values = [0.; N];
+    for (index, argument) in arguments.iter().enumerate() {
+        let value = argument
+            .parse()
+            .map_err(|_| StingrayError::Parse(format!("invalid number '{argument}'")))?;
+        if let Some(slot) = values.get_mut(index) {
+            *slot = value;
+        }
+    }
+    Ok(values)
+}
+
+/// Resolves a 1-based or negative (relative to the end) OBJ index into `len` items.
+fn resolve_index(argument: &str, len: usize, kind: &str) -> Result<usize, StingrayError> {
+    let index: i64 = argument
+        .parse()
+        .map_err(|_| StingrayError::Parse(format!("invalid {kind} index '{argument}'")))?;
+    let resolved = match index {
+        0 => None,
+        i if i > 0 => Some(i as usize - 1),
+        i => len.checked_sub(i.unsigned_abs() as usize),
+    };
+    match resolved {
+        Some(resolved) if resolved < len => Ok(resolved),
+        _ => Err(StingrayError::Parse(format!(
+            "{kind} index {index} out of range, {len} defined so far"
+        ))),
+    }
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face vertex.
+fn parse_face_vertex(
+    obj: &ObjFile,
+    argument: &str,
+) -> Result<(Point, Option<Vector>), StingrayError> {
+    let mut parts = argument.split('/');
+    let vertex = parts.next().unwrap_or("");
+    let texture = parts.next().filter(|s| !s.is_empty());
+    let normal = parts.next().filter(|s| !s.is_empty());
+    if parts.next().is_some() {
+        return Err(StingrayError::Parse(format!(
+            "invalid face vertex '{argument}'"
+        )));
+    }
+
+    let point = obj.vertices[resolve_index(vertex, obj.vertices.len(), "vertex")?];
+    if let Some(texture) = texture {
+        resolve_index(texture, obj.texture_coordinates.len(), "texture")?;
+    }
+    let normal = match normal {
+        Some(normal) => Some(obj.normals[resolve_index(normal, obj.normals.len(), "normal")?]),
+        None => None,
+    };
+    Ok((point, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geo::{objects::geometry::Geometry, ray::Ray},
+        test::utils::ts,
+    };
+
+    #[test]
+    fn test_ignores_unrecognized_lines() {
+        let input = "There was a young lady named Bright\n\
+                     who traveled much faster than light.\n\
+                     # a comment\n\
+                     \n\
+                     mtllib scene.mtl";
+        let obj = parse_obj(input).unwrap();
+
+        let lines: Vec<usize> = obj.ignored.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn test_vertex_records() {
+        let input = "v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0";
+        let obj = parse_obj(input).unwrap();
+
+        assert_eq!(
+            obj.vertices,
+            vec![
+                Point::new(-1., 1., 0.),
+                Point::new(-1., 0.5, 0.),
+                Point::new(1., 0., 0.),
+                Point::new(1., 1., 0.),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional_coordinates() {
+        // w, vertex colours, and texture coordinates with one to three numbers
+        let input = "v 1 2 3 1\nv 1 2 3 0.5 0.25 1\nv 1 2 3 1 0.5 0.25 1\n\
+                     vt 0.5\nvt 0.5 1\nvt 0.5 1 0";
+        let obj = parse_obj(input).unwrap();
+
+        assert_eq!(obj.vertices, vec![Point::new(1., 2., 3.); 3]);
+        assert_eq!(
+            obj.texture_coordinates,
+            vec![(0.5, 0.), (0.5, 1.), (0.5, 1.)]
+        );
+    }
+
+    #[test]
+    fn test_triangle_faces() {
+        let input = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4";
+        let obj = parse_obj(input).unwrap();
+        let v = &obj.vertices;
+
+        assert_eq!(obj.default_group.len(), 2);
+        assert_eq!(obj.default_group[0].vertices, [v[0], v[1], v[2]]);
+        assert_eq!(obj.default_group[1].vertices, [v[0], v[2], v[3]]);
+        assert!(obj.default_group[0].normals.is_none());
+    }
+
+    #[test]
+    fn test_polygon_fan_triangulation() {
+        let input = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5";
+        let obj = parse_obj(input).unwrap();
+        let v = &obj.vertices;
+
+        assert_eq!(obj.default_group.len(), 3);
+        assert_eq!(obj.default_group[0].vertices, [v[0], v[1], v[2]]);
+        assert_eq!(obj.default_group[1].vertices, [v[0], v[2], v[3]]);
+        assert_eq!(obj.default_group[2].vertices, [v[0], v[3], v[4]]);
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let input = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                     g FirstGroup\nf 1 2 3\no SecondGroup\nf 1 3 4\ng FirstGroup\nf 2 3 4";
+        let obj = parse_obj(input).unwrap();
+
+        assert!(obj.default_group.is_empty());
+        assert_eq!(obj.groups.len(), 2);
+        assert_eq!(obj.groups[0].name, "FirstGroup");
+        assert_eq!(obj.groups[0].faces.len(), 2);
+        assert_eq!(obj.groups[1].name, "SecondGroup");
+        assert_eq!(obj.groups[1].faces.len(), 1);
+        assert_eq!(obj.triangle_count(), 3);
+    }
+
+    #[test]
+    fn test_vertex_normals_and_texture_coordinates() {
+        let input = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                     vn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+                     vt 0.5 1\nvt 0 0 0\nvt 1 0\n\
+                     f 1//3 2//1 3//2\nf 1/1/3 2/2/1 3/3/2\nf 1/1 2/2 3/3";
+        let obj = parse_obj(input).unwrap();
+        let n = &obj.normals;
+
+        assert_eq!(obj.texture_coordinates, vec![(0.5, 1.), (0., 0.), (1., 0.)]);
+        assert_eq!(obj.default_group[0].normals, Some([n[2], n[0], n[1]]));
+        assert_eq!(obj.default_group[1], obj.default_group[0]);
+        assert!(obj.default_group[2].normals.is_none());
+    }
+
+    #[test]
+    fn test_negative_indices() {
+        let input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nf -3 -2 -1\nv 5 5 5\nf 1 -2 -1";
+        let obj = parse_obj(input).unwrap();
+        let v = &obj.vertices;
+
+        assert_eq!(obj.default_group[0].vertices, [v[0], v[1], v[2]]);
+        assert_eq!(obj.default_group[1].vertices, [v[0], v[2], v[3]]);
+    }
+
+    #[test]
+    fn test_parse_errors_report_line() {
+        let cases = [
+            ("v 1 2\n", "line 1: expected 3 to 7 numbers, found 2"),
+            ("vn 1 2 3 4\n", "line 1: expected 3 numbers, found 4"),
+            ("vt\n", "line 1: expected 1 to 3 numbers, found 0"),
+            ("v 1 2 3 1 0 x\n", "line 1: invalid number 'x'"),
+            ("v 1 2 x\n", "line 1: invalid number 'x'"),
+            (
+                "v 0 0 0\nv 1 0 0\nf 1 2\n",
+                "line 3: face needs at least 3 vertices, found 2",
+            ),
+            (
+                "v 0 0 0\nf 1 2 3\n",
+                "line 2: vertex index 2 out of range, 1 defined so far",
+            ),
+            (
+                "v 0 0 0\nf 0 1 1\n",
+                "line 2: vertex index 0 out of range, 1 defined so far",
+            ),
+            (
+                "v 0 0 0\nf 1//1 1 1\n",
+                "line 2: normal index 1 out of range, 0 defined so far",
+            ),
+            (
+                "v 0 0 0\nf -2 1 1\n",
+                "line 2: vertex index -2 out of range, 1 defined so far",
+            ),
+            ("\n\ng\n", "line 3: 'g' record without a name"),
+        ];
+
+        for (input, message) in cases {
+            assert_eq!(parse_obj(input).unwrap_err().report(), message);
+        }
+    }
+
+    #[test]
+    fn test_to_group() {
+        let input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 -1\n\
+                     f 1 2 3\ng Smooth\nf 1//1 2//1 3//1";
+        let obj = parse_obj(input).unwrap();
+        let group = obj.to_group();
+        let ray = Ray::new(Point::new(0., 0.5, -2.), Vector::new(0., 0., 1.));
+
+        // The flat triangle directly, then the subgroup holding the smooth one
+        assert_eq!(group.len(), 2);
+        assert_eq!(ts(&group.intersect(&ray).unwrap()), vec![2., 2.]);
+    }
+}
@@ -0,0 +1,964 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    f64::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use yaml_rust::{yaml::Hash, Yaml, YamlLoader};
+
+use crate::{
+    camera::Camera,
+    canvas::scene_object::SceneObject,
+    color::Color,
+    error::StingrayError,
+    geo::{
+        matrix::Rotation,
+        matrix4::Matrix4,
+        objects::{
+            cone::Cone,
+            csg::{Csg, CsgOperation},
+            cube::Cube,
+            cylinder::Cylinder,
+            geometry::Geometry,
+            group::Group,
+            painted::Painted,
+            plane::Plane,
+            sphere::Sphere,
+        },
+        point::Point,
+        transform::Transform,
+        vector::Vector,
+    },
+    lighting::{Material, PointLight},
+    obj::ObjFile,
+    world::World,
+};
+
+/// A world and the camera to render it with, loaded from a YAML scene file.
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+    /// Settings in the file that the renderer does not use yet, such as
+    /// reflective materials, each naming its node.
+    pub warnings: Vec<String>,
+}
+
+impl Scene {
+    pub fn load(path: &str) -> Result<Scene, StingrayError> {
+        let input = fs::read_to_string(path).map_err(|source| StingrayError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        let base_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+        Scene::parse(&input, base_dir)
+    }
+
+    /// Parses a scene description. Files referenced by the scene, such as
+    /// OBJ meshes, are resolved relative to `base_dir`.
+    ///
+    /// The scene is a list of `add:` items (`camera`, `light` or a shape) and
+    /// `define:` items naming materials, transforms or shapes for reuse.
+    /// Materials take `color`, `ambient`, `diffuse`, `specular` and
+    /// `shininess`; `reflective`, `transparency` and `refractive-index` are
+    /// accepted but ignored with a warning. Children of groups and CSG
+    /// shapes may have a `material` of their own, which overrides the one
+    /// given to the whole shape. Errors name the offending node, e.g. `scene[3].material.ambient`.
+    pub fn parse(input: &str, base_dir: &Path) -> Result<Scene, StingrayError> {
+        let documents = YamlLoader::load_from_str(input)
+            .map_err(|e| StingrayError::Parse(format!("scene: {e}")))?;
+        let items = match documents.first() {
+            Some(Yaml::Array(items)) => items,
+            Some(_) => {
+                return Err(StingrayError::Parse(
+                    "scene: expected a list of items".to_string(),
+                ))
+            }
+            None => return Err(StingrayError::Parse("scene: file is empty".to_string())),
+        };
+
+        let mut loader = Loader {
+            defines: HashMap::new(),
+            resolving: RefCell::new(vec![]),
+            warnings: RefCell::new(vec![]),
+            base_dir: base_dir.to_path_buf(),
+        };
+        let mut world = World::new();
+        let mut camera = None;
+
+        for (index, item) in items.iter().enumerate() {
+            let path = format!("scene[{index}]");
+            let map = mapping(item, &path)?;
+
+            if let Some(name) = get(map, "define") {
+                check_keys(map, &["define", "extend", "value"], &path)?;
+                let name = string(name, &format!("{path}.define"))?;
+                loader.define(name, map, &path)?;
+                continue;
+            }
+
+            let kind = string(
+                get(map, "add").ok_or(StingrayError::Parse(format!(
+                    "{path}: expected 'add' or 'define'"
+                )))?,
+                &format!("{path}.add"),
+            )?;
+            match kind {
+                "camera" => camera = Some(loader.camera(map, &path)?),
+                "light" => world.add_light(loader.light(map, &path)?),
+                _ => {
+                    let (geometry, material) = loader.shape(item, &path)?;
+                    world.push_object(SceneObject::new(geometry, material.unwrap_or_default()));
+                }
+            }
+        }
+
+        let camera = camera.ok_or(StingrayError::Parse(
+            "scene: no camera was added".to_string(),
+        ))?;
+        Ok(Scene {
+            world,
+            camera,
+            warnings: loader.warnings.into_inner(),
+        })
+    }
+}
+
+struct Loader {
+    defines: HashMap<String, Yaml>,
+    /// Names being expanded, outermost first, so a define that uses
+    /// itself is an error rather than endless recursion.
+    resolving: RefCell<Vec<String>>,
+    warnings: RefCell<Vec<String>>,
+    base_dir: PathBuf,
+}
+
+impl Loader {
+    fn define(&mut self, name: &str, map: &Hash, path: &str) -> Result<(), StingrayError> {
+        let value = get(map, "value").ok_or(StingrayError::Parse(format!(
+            "{path}: define without a 'value'"
+        )))?;
+
+        let value = match get(map, "extend") {
+            Some(extend) => {
+                let extend_path = format!("{path}.extend");
+                let base = self.lookup(string(extend, &extend_path)?, &extend_path)?;
+                let base = mapping(base, &extend_path)?;
+                let mut merged = base.clone();
+                for (key, value) in mapping(value, &format!("{path}.value"))? {
+                    merged.insert(key.clone(), value.clone());
+                }
+                Yaml::Hash(merged)
+            }
+            None => value.clone(),
+        };
+
+        self.defines.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str, path: &str) -> Result<&Yaml, StingrayError> {
+        self.defines.get(name).ok_or(StingrayError::Parse(format!(
+            "{path}: '{name}' has not been defined"
+        )))
+    }
+
+    /// Builds `name`'s definition with `build`, failing if `name` is
+    /// already being expanded further up.
+    fn follow<T>(
+        &self,
+        name: &str,
+        path: &str,
+        build: impl FnOnce(&Yaml) -> Result<T, StingrayError>,
+    ) -> Result<T, StingrayError> {
+        if self.resolving.borrow().iter().any(|outer| outer == name) {
+            return Err(StingrayError::Parse(format!(
+                "{path}: '{name}' refers to itself"
+            )));
+        }
+        let value = self.lookup(name, path)?;
+        self.resolving.borrow_mut().push(name.to_string());
+        let result = build(value);
+        self.resolving.borrow_mut().pop();
+        result
+    }
+
+    fn camera(&self, map: &Hash, path: &str) -> Result<Camera, StingrayError> {
+        check_keys(
+            map,
+            &[
+                "add",
+                "width",
+                "height",
+                "field-of-view",
+                "from",
+                "to",
+                "up",
+            ],
+            path,
+        )?;
+        let width = required(map, "width", path, positive_integer)?;
+        let height = required(map, "height", path, positive_integer)?;
+        let field_of_view = required(map, "field-of-view", path, number)?;
+        if field_of_view <= 0. || field_of_view >= PI {
+            return Err(StingrayError::Parse(format!(
+                "{path}.field-of-view: must be between 0 and pi radians"
+            )));
+        }
+        let from = required(map, "from", path, triple)?;
+        let to = required(map, "to", path, triple)?;
+        let up = required(map, "up", path, triple)?;
+
+        let mut camera = Camera::new(width, height, field_of_view);
+        camera.set_transform(Matrix4::view_transform(
+            Point::new(from.0, from.1, from.2),
+            Point::new(to.0, to.1, to.2),
+            Vector::new(up.0, up.1, up.2),
+        ));
+        Ok(camera)
+    }
+
+    fn light(&self, map: &Hash, path: &str) -> Result<PointLight, StingrayError> {
+        check_keys(map, &["add", "at", "intensity"], path)?;
+        let at = required(map, "at", path, triple)?;
+        let intensity = required(map, "intensity", path, triple)?;
+        Ok(PointLight::new(
+            Point::new(at.0, at.1, at.2),
+            Color::new(intensity.0, intensity.1, intensity.2),
+        ))
+    }
+
+    /// Builds a shape and, if it names one, its material.
+    fn shape(
+        &self,
+        node: &Yaml,
+        path: &str,
+    ) -> Result<(Box<dyn Geometry>, Option<Material>), StingrayError> {
+        let map = mapping(node, path)?;
+        let add_path = format!("{path}.add");
+        let kind = string(
+            get(map, "add").ok_or(StingrayError::Parse(format!("{path}: missing 'add'")))?,
+            &add_path,
+        )?;
+
+        let mut common = vec!["add", "material", "transform"];
+        let mut geometry: Box<dyn Geometry> = match kind {
+            "sphere" => Box::new(Sphere::unit_sphere()),
+            "plane" => Box::new(Plane::new()),
+            "cube" => Box::new(Cube::new()),
+            "cylinder" | "cone" => {
+                common.extend(["min", "max", "closed"]);
+                let minimum = optional(map, "min", path, number)?.unwrap_or(f64::NEG_INFINITY);
+                let maximum = optional(map, "max", path, number)?.unwrap_or(f64::INFINITY);
+                let closed = optional(map, "closed", path, boolean)?.unwrap_or(false);
+                if kind == "cylinder" {
+                    Box::new(Cylinder::truncated(minimum, maximum, closed))
+                } else {
+                    Box::new(Cone::truncated(minimum, maximum, closed))
+                }
+            }
+            "group" => {
+                common.push("children");
+                Box::new(self.group(map, path)?)
+            }
+            "obj" => {
+                common.push("file");
+                let file = required(map, "file", path, |node, path| {
+                    string(node, path).map(str::to_string)
+                })?;
+                let file = self.base_dir.join(file);
+                let obj = ObjFile::load(&file.to_string_lossy())
+                    .map_err(|e| e.context(format!("{path}.file")))?;
+                Box::new(obj.to_group())
+            }
+            "csg" => {
+                common.extend(["operation", "left", "right"]);
+                Box::new(self.csg(map, path)?)
+            }
+            name => {
+                // A shape defined earlier, with any keys given here overriding it
+                let Ok(Yaml::Hash(_)) = self.lookup(name, &add_path) else {
+                    return Err(StingrayError::Parse(format!(
+                        "{add_path}: unknown shape '{name}'"
+                    )));
+                };
+                return self.follow(name, &add_path, |template| {
+                    let mut merged = mapping(template, &add_path)?.clone();
+                    for (key, value) in map.iter().filter(|(key, _)| key.as_str() != Some("add")) {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                    self.shape(&Yaml::Hash(merged), path)
+                });
+            }
+        };
+        check_keys(map, &common, path)?;
+
+        if let Some(transform) = get(map, "transform") {
+            geometry.set_transform(self.transform(transform, &format!("{path}.transform"))?);
+        }
+        let material = match get(map, "material") {
+            Some(material) => Some(self.material(material, &format!("{path}.material"))?),
+            None => None,
+        };
+        Ok((geometry, material))
+    }
+
+    fn group(&self, map: &Hash, path: &str) -> Result<Group, StingrayError> {
+        let children_path = format!("{path}.children");
+        let children = match get(map, "children") {
+            Some(Yaml::Array(children)) => children.as_slice(),
+            Some(_) => {
+                return Err(StingrayError::Parse(format!(
+                    "{children_path}: expected a list of shapes"
+                )))
+            }
+            None => &[],
+        };
+
+        let mut group = Group::new();
+        for (index, child) in children.iter().enumerate() {
+            let child_path = format!("{children_path}[{index}]");
+            let (child, material) = self.shape(child, &child_path)?;
+            match material {
+                Some(material) => group.add_child(Painted::new(child, material)),
+                None => group.add_child(child),
+            }
+        }
+        Ok(group)
+    }
+
+    fn csg(&self, map: &Hash, path: &str) -> Result<Csg, StingrayError> {
+        let operation = match required(map, "operation", path, |node, path| {
+            string(node, path).map(str::to_string)
+        })?
+        .as_str()
+        {
+            "union" => CsgOperation::Union,
+            "intersection" => CsgOperation::Intersection,
+            "difference" => CsgOperation::Difference,
+            other => {
+                return Err(StingrayError::Parse(format!(
+                    "{path}.operation: unknown operation '{other}', expected union, intersection or difference"
+                )))
+            }
+        };
+
+        let mut children = vec![];
+        for side in ["left", "right"] {
+            let side_path = format!("{path}.{side}");
+            let node =
+                get(map, side).ok_or(StingrayError::Parse(format!("{path}: missing '{side}'")))?;
+            let (child, material) = self.shape(node, &side_path)?;
+            children.push(match material {
+                Some(material) => Box::new(Painted::new(child, material)),
+                None => child,
+            });
+        }
+        let right = children.pop().expect("two children");
+        let left = children.pop().expect("two children");
+        Ok(Csg::new(operation, left, right))
+    }
+
+    fn material(&self, node: &Yaml, path: &str) -> Result<Material, StingrayError> {
+        if let Yaml::String(name) = node {
+            return self.follow(name, path, |value| self.material(value, path));
+        }
+        let map = mapping(node, path)?;
+        check_keys(
+            map,
+            &[
+                "color",
+                "ambient",
+                "diffuse",
+                "specular",
+                "shininess",
+                "reflective",
+                "transparency",
+                "refractive-index",
+            ],
+            path,
+        )?;
+        // Common in scene files written for fuller renderers
+        for key in ["reflective", "transparency", "refractive-index"] {
+            if optional(map, key, path, number)?.is_some() {
+                self.warnings
+                    .borrow_mut()
+                    .push(format!("{path}.{key}: not supported yet, ignored"));
+            }
+        }
+
+        let default = Material::default();
+        Material::try_new(
+            optional(map, "color", path, color)?.unwrap_or(default.color),
+            optional(map, "ambient", path, number)?.unwrap_or(default.ambient),
+            optional(map, "diffuse", path, number)?.unwrap_or(default.diffuse),
+            optional(map, "specular", path, number)?.unwrap_or(default.specular),
+            optional(map, "shininess", path, number)?.unwrap_or(default.shininess),
+        )
+        .map_err(|error| error.context(path))
+    }
+
+    /// Combines a list of transforms, applied in the order they are listed.
+    fn transform(&self, node: &Yaml, path: &str) -> Result<Matrix4, StingrayError> {
+        let Yaml::Array(steps) = node else {
+            return Err(StingrayError::Parse(format!(
+                "{path}: expected a list of transforms"
+            )));
+        };
+
+        let mut result = Transform::identity();
+        for (index, step) in steps.iter().enumerate() {
+            let step_path = format!("{path}[{index}]");
+            let matrix = match step {
+                Yaml::String(name) => {
+                    self.follow(name, &step_path, |value| self.transform(value, &step_path))?
+                }
+                Yaml::Array(parts) => {
+                    let operation = string(
+                        parts.first().unwrap_or(&Yaml::Null),
+                        &format!("{step_path}[0]"),
+                    )?;
+                    let arguments = parts[1..]
+                        .iter()
+                        .enumerate()
+                        .map(|(i, part)| number(part, &format!("{step_path}[{}]", i + 1)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    transform_step(operation, &arguments, &step_path)?
+                }
+                _ => {
+                    return Err(StingrayError::Parse(format!(
+                        "{step_path}: expected [operation, arguments...] or a defined transform"
+                    )))
+                }
+            };
+            result = result.then(matrix);
+        }
+        Ok(result.matrix())
+    }
+}
+
+fn transform_step(
+    operation: &str,
+    arguments: &[f64],
+    path: &str,
+) -> Result<Matrix4, StingrayError> {
+    let expect = |count: usize| {
+        if arguments.len() == count {
+            Ok(())
+        } else {
+            Err(StingrayError::Parse(format!(
+                "{path}: '{operation}' takes {count} arguments, found {}",
+                arguments.len()
+            )))
+        }
+    };
+
+    match operation {
+        "translate" => {
+            expect(3)?;
+            Ok(Matrix4::translation(
+                arguments[0],
+                arguments[1],
+                arguments[2],
+            ))
+        }
+        "scale" => {
+            expect(3)?;
+            Ok(Matrix4::scaling(arguments[0], arguments[1], arguments[2]))
+        }
+        "rotate-x" | "rotate-y" | "rotate-z" => {
+            expect(1)?;
+            let axis = match operation {
+                "rotate-x" => Rotation::X,
+                "rotate-y" => Rotation::Y,
+                _ => Rotation::Z,
+            };
+            Ok(Matrix4::rotation(arguments[0], axis))
+        }
+        "shear" => {
+            expect(6)?;
+            Ok(Matrix4::shearing(
+                arguments[0],
+                arguments[1],
+                arguments[2],
+                arguments[3],
+                arguments[4],
+                arguments[5],
+            ))
+        }
+        _ => Err(StingrayError::Parse(format!(
+            "{path}: unknown transform '{operation}'"
+        ))),
+    }
+}
+
+fn get<'a>(map: &'a Hash, key: &str) -> Option<&'a Yaml> {
+    map.get(&Yaml::String(key.to_string()))
+}
+
+fn check_keys(map: &Hash, allowed: &[&str], path: &str) -> Result<(), StingrayError> {
+    for key in map.keys() {
+        match key.as_str() {
+            Some(key) if allowed.contains(&key) => {}
+            Some(key) => {
+                return Err(StingrayError::Parse(format!(
+                    "{path}: unexpected key '{key}'"
+                )))
+            }
+            None => {
+                return Err(StingrayError::Parse(format!(
+                    "{path}: keys must be strings"
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn required<T>(
+    map: &Hash,
+    key: &str,
+    path: &str,
+    parse: impl Fn(&Yaml, &str) -> Result<T, StingrayError>,
+) -> Result<T, StingrayError> {
+    let node = get(map, key).ok_or(StingrayError::Parse(format!("{path}: missing '{key}'")))?;
+    parse(node, &format!("{path}.{key}"))
+}
+
+fn optional<T>(
+    map: &Hash,
+    key: &str,
+    path: &str,
+    parse: impl Fn(&Yaml, &str) -> Result<T, StingrayError>,
+) -> Result<Option<T>, StingrayError> {
+    get(map, key)
+        .map(|node| parse(node, &format!("{path}.{key}")))
+        .transpose()
+}
+
+fn mapping<'a>(node: &'a Yaml, path: &str) -> Result<&'a Hash, StingrayError> {
+    match node {
+        Yaml::Hash(map) => Ok(map),
+        _ => Err(StingrayError::Parse(format!("{path}: expected a mapping"))),
+    }
+}
+
+fn string<'a>(node: &'a Yaml, path: &str) -> Result<&'a str, StingrayError> {
+    node.as_str()
+        .ok_or(StingrayError::Parse(format!("{path}: expected a string")))
+}
+
+fn boolean(node: &Yaml, path: &str) -> Result<bool, StingrayError> {
+    node.as_bool().ok_or(StingrayError::Parse(format!(
+        "{path}: expected true or false"
+    )))
+}
+
+fn number(node: &Yaml, path: &str) -> Result<f64, StingrayError> {
+    match node {
+        Yaml::Integer(value) => Ok(*value as f64),
+        Yaml::Real(_) => node
+            .as_f64()
+            .ok_or(StingrayError::Parse(format!("{path}: invalid number"))),
+        _ => Err(StingrayError::Parse(format!("{path}: expected a number"))),
+    }
+}
+
+fn positive_integer(node: &Yaml, path: &str) -> Result<u32, StingrayError> {
+    match node {
+        Yaml::Integer(value) if *value > 0 && *value <= u32::MAX as i64 => Ok(*value as u32),
+        _ => Err(StingrayError::Parse(format!(
+            "{path}: expected a positive whole number"
+        ))),
+    }
+}
+
+/// A linear color as a list of 3 numbers, or an sRGB hex code such as
+/// "#ff8800" as artists hand them over.
+fn color(node: &Yaml, path: &str) -> Result<Color, StingrayError> {
+    match node {
+        Yaml::String(hex) => Color::from_hex(hex).map_err(|e| e.context(path)),
+        Yaml::Array(_) => triple(node, path).map(|(r, g, b)| Color::new(r, g, b)),
+        _ => Err(StingrayError::Parse(format!(
+            "{path}: expected a list of 3 numbers or a hex color"
+        ))),
+    }
+}
+
+fn triple(node: &Yaml, path: &str) -> Result<(f64, f64, f64), StingrayError> {
+    match node {
+        Yaml::Array(values) if values.len() == 3 => Ok((
+            number(&values[0], &format!("{path}[0]"))?,
+            number(&values[1], &format!("{path}[1]"))?,
+            number(&values[2], &format!("{path}[2]"))?,
+        )),
+        _ => Err(StingrayError::Parse(format!(
+            "{path}: expected a list of 3 numbers"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{geo::ray::Ray, test::utils::ts, traits::SpecificRound};
+
+    const CAMERA: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+";
+
+    fn parse(body: &str) -> Result<Scene, StingrayError> {
+        Scene::parse(&format!("{CAMERA}{body}"), Path::new("."))
+    }
+
+    #[test]
+    fn test_camera_and_light() {
+        let scene = parse(
+            "
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 0.5, 1]
+",
+        )
+        .unwrap();
+
+        assert_eq!(scene.camera.hsize, 100);
+        assert_eq!(scene.camera.vsize, 50);
+        assert_eq!(scene.camera.field_of_view, 0.785);
+        assert_eq!(scene.world.lights.len(), 1);
+        assert_eq!(scene.world.lights[0].position, Point::new(-10., 10., -10.));
+        assert_eq!(scene.world.lights[0].color.g, 0.5);
+    }
+
+    #[test]
+    fn test_shapes_with_transforms_in_order() {
+        let scene = parse(
+            "
+- add: sphere
+  transform:
+    - [scale, 2, 2, 2]
+    - [translate, 0, 0, 10]
+- add: plane
+- add: cube
+- add: cylinder
+  min: 0
+  max: 1
+  closed: true
+- add: cone
+",
+        )
+        .unwrap();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+
+        assert_eq!(scene.world.objects().len(), 5);
+        // Scaled first, then moved, so the sphere spans z = 8 to 12
+        let sphere = &scene.world.objects()[0].geometry;
+        assert_eq!(ts(&sphere.intersect(&ray).unwrap()), vec![8., 12.]);
+    }
+
+    #[test]
+    fn test_material_defines_and_extend() {
+        let scene = parse(
+            "
+- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    ambient: 0.1
+    specular: 0.0
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.5, 0.8, 0.9]
+- add: sphere
+  material: blue-material
+- add: sphere
+  material:
+    shininess: 50
+- add: sphere
+  material:
+    color: '#ff8800'
+",
+        )
+        .unwrap();
+        let blue = scene.world.objects()[0].material;
+        let inline = scene.world.objects()[1].material;
+        let hex = scene.world.objects()[2].material;
+
+        assert_eq!((blue.color.r, blue.color.g, blue.color.b), (0.5, 0.8, 0.9));
+        assert_eq!(blue.diffuse, 0.7);
+        assert_eq!(blue.specular, 0.);
+        assert_eq!(inline.shininess, 50.);
+        assert_eq!(inline.diffuse, Material::default().diffuse);
+        // Hex codes are sRGB, so they reach the material linearised
+        assert_eq!(hex.color.r, 1.);
+        assert_eq!(hex.color.g.specific_round(4), 0.2462);
+    }
+
+    #[test]
+    fn test_unsupported_material_keys_warn() {
+        let scene =
+            parse("- add: sphere\n  material:\n    reflective: 0.5\n    refractive-index: 1.5\n")
+                .unwrap();
+
+        assert_eq!(
+            scene.warnings,
+            vec![
+                "scene[1].material.reflective: not supported yet, ignored",
+                "scene[1].material.refractive-index: not supported yet, ignored",
+            ]
+        );
+        assert!(parse("- add: sphere\n").unwrap().warnings.is_empty());
+    }
+
+    #[test]
+    fn test_transform_defines() {
+        let scene = parse(
+            "
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+- define: large-object
+  value:
+    - standard-transform
+    - [scale, 4, 4, 4]
+- add: cube
+  transform:
+    - large-object
+",
+        )
+        .unwrap();
+        let bounds = scene.world.objects()[0].geometry.parent_space_bounds();
+
+        assert_eq!(bounds.min, Point::new(0., -4., 0.));
+        assert_eq!(bounds.max, Point::new(4., 0., 4.));
+    }
+
+    #[test]
+    fn test_shape_defines() {
+        let scene = parse(
+            "
+- define: pillar
+  value:
+    add: cylinder
+    min: 0
+    max: 3
+    closed: true
+- add: pillar
+  transform:
+    - [translate, 5, 0, 0]
+",
+        )
+        .unwrap();
+        let bounds = scene.world.objects()[0].geometry.parent_space_bounds();
+
+        assert_eq!(bounds.min, Point::new(4., 0., -1.));
+        assert_eq!(bounds.max, Point::new(6., 3., 1.));
+    }
+
+    #[test]
+    fn test_groups_and_csg() {
+        let scene = parse(
+            "
+- add: group
+  transform:
+    - [translate, 0, 0, 10]
+  children:
+    - add: sphere
+    - add: cube
+      transform:
+        - [translate, 3, 0, 0]
+- add: csg
+  operation: difference
+  left:
+    add: cube
+  right:
+    add: sphere
+    transform:
+      - [scale, 1.2, 1.2, 1.2]
+",
+        )
+        .unwrap();
+        let through_group = Ray::new(Point::new(3., 0., 0.), Vector::new(0., 0., 1.));
+        let group = &scene.world.objects()[0].geometry;
+        let csg = &scene.world.objects()[1].geometry;
+        let diagonal = Ray::new(Point::new(-5., -5., -5.), Vector::new(1., 1., 1.).norm());
+
+        assert_eq!(ts(&group.intersect(&through_group).unwrap()), vec![9., 11.]);
+        // Only the corners of the cube poke out of the sphere
+        assert_eq!(csg.intersect(&diagonal).unwrap().len(), 4);
+        assert_eq!(
+            csg.intersect(&diagonal).unwrap()[0].t.specific_round(4),
+            (75_f64.sqrt() - 3_f64.sqrt()).specific_round(4)
+        );
+    }
+
+    #[test]
+    fn test_child_materials() {
+        let scene = parse(
+            "
+- add: group
+  material:
+    color: [0, 0, 1]
+  children:
+    - add: sphere
+      material:
+        color: [1, 0, 0]
+    - add: sphere
+      transform:
+        - [translate, 0, 0, 5]
+",
+        )
+        .unwrap();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = scene.world.intersect_world(&ray).unwrap();
+        let red = |i: usize| xs[i].material.unwrap().color.r;
+
+        assert_eq!(ts(&xs), vec![4., 6., 9., 11.]);
+        assert_eq!((red(0), red(1), red(2), red(3)), (1., 1., 0., 0.));
+    }
+
+    #[test]
+    fn test_obj_files_relative_to_scene() {
+        let dir = std::env::temp_dir().join("stingray-scene-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("triangle.obj"),
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let scene = Scene::parse(
+            &format!("{CAMERA}\n- add: obj\n  file: triangle.obj\n"),
+            &dir,
+        )
+        .unwrap();
+        let ray = Ray::new(Point::new(0., 0.5, -2.), Vector::new(0., 0., 1.));
+
+        assert_eq!(
+            ts(&scene.world.objects()[0].geometry.intersect(&ray).unwrap()),
+            vec![2.]
+        );
+    }
+
+    #[test]
+    fn test_errors_point_to_node() {
+        let cases = [
+            ("- add: teapot\n", "scene[1].add: unknown shape 'teapot'"),
+            (
+                "- add: sphere\n  material:\n    ambient: 2\n",
+                "scene[1].material: ambient: must be between 0 and 1, found 2",
+            ),
+            (
+                "- add: sphere\n  material:\n    shininess: 0\n",
+                "scene[1].material: shininess: must be greater than 0, found 0",
+            ),
+            (
+                "- add: sphere\n  material: shiny\n",
+                "scene[1].material: 'shiny' has not been defined",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [scale, 1, 2]\n",
+                "scene[1].transform[0]: 'scale' takes 3 arguments, found 2",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [rotate-x, fast]\n",
+                "scene[1].transform[0][1]: expected a number",
+            ),
+            (
+                "- add: light\n  at: [0, 0]\n  intensity: [1, 1, 1]\n",
+                "scene[1].at: expected a list of 3 numbers",
+            ),
+            (
+                "- add: cube\n  radius: 2\n",
+                "scene[1]: unexpected key 'radius'",
+            ),
+            (
+                "- add: cube\n  material:\n    color: '#ff88'\n",
+                "scene[1].material.color: invalid hex color '#ff88', expected 3 or 6 digits",
+            ),
+            (
+                "- add: obj\n  file: missing.obj\n",
+                "scene[1].file: ./missing.obj: No such file or directory (os error 2)",
+            ),
+        ];
+
+        for (body, message) in cases {
+            match parse(body) {
+                Ok(_) => panic!("expected an error for {body}"),
+                Err(error) => assert_eq!(error.report(), message),
+            }
+        }
+    }
+
+    #[test]
+    fn test_self_references() {
+        let cases = [
+            (
+                "- define: t\n  value: [t]\n- add: sphere\n  transform: [t]\n",
+                "scene[2].transform[0][0]: 't' refers to itself",
+            ),
+            (
+                "- define: m\n  value: m\n- add: sphere\n  material: m\n",
+                "scene[2].material: 'm' refers to itself",
+            ),
+            (
+                "- define: s\n  value:\n    add: s\n- add: s\n",
+                "scene[2].add: 's' refers to itself",
+            ),
+        ];
+
+        for (body, message) in cases {
+            match parse(body) {
+                Ok(_) => panic!("expected an error for {body}"),
+                Err(error) => {
+                    assert_eq!(error.to_string(), message);
+                    assert!(matches!(error, StingrayError::Parse(_)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let Err(material) = parse("- add: sphere\n  material:\n    ambient: 2\n") else {
+            panic!("expected the material to be rejected");
+        };
+        assert!(matches!(
+            material.root_cause(),
+            StingrayError::InvalidMaterial(_)
+        ));
+
+        let key = parse("- add: cube\n  radius: 2\n");
+        assert!(matches!(key, Err(StingrayError::Parse(_))));
+
+        let Err(missing) = parse("- add: obj\n  file: missing.obj\n") else {
+            panic!("expected the missing mesh to fail");
+        };
+        match missing.root_cause() {
+            StingrayError::Io { path, source } => {
+                assert_eq!(path, "./missing.obj");
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            other => panic!("expected an io error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_scene_without_camera() {
+        let result = Scene::parse("- add: sphere\n", Path::new("."));
+
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "scene: no camera was added"
+        );
+    }
+}
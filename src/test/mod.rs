@@ -1,5 +1,6 @@
+#[cfg(test)]
 pub mod utils;
-use crate::traits::ApproxEq;
+
 #[macro_export]
 macro_rules! assert_approx_eq {
     ($left:expr, $right:expr) => {{
@@ -21,9 +22,31 @@ macro_rules! assert_fractional {
         let val = $val;
 
         assert!(
-            val > 0.0 && val < 1.0,
+            (0.0..=1.0).contains(&val),
             "assertion failed: value not fractional\n  value: `{:?}`\n ",
             val,
         );
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_fractional_includes_bounds() {
+        assert_fractional!(0.0);
+        assert_fractional!(0.5);
+        assert_fractional!(1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "value not fractional")]
+    fn test_fractional_below_zero() {
+        assert_fractional!(-0.001);
+    }
+
+    #[test]
+    #[should_panic(expected = "value not fractional")]
+    fn test_fractional_above_one() {
+        assert_fractional!(1.001);
+    }
+}
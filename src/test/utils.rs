@@ -1,4 +1,14 @@
-use crate::geo::{point::Point, vector::Vector};
+use crate::{
+    color::Color,
+    geo::{
+        matrix4::Matrix4,
+        objects::{intersection::Intersections, sphere::Sphere},
+        point::Point,
+        vector::Vector,
+    },
+    lighting::{Material, PointLight},
+    world::World,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Projectile {
@@ -29,6 +39,30 @@ impl Environment {
     }
 }
 
+/// The `t` values of a set of intersections, in order.
+pub fn ts(intersections: &Intersections) -> Vec<f64> {
+    intersections.iter().map(|i| i.t).collect()
+}
+
+/// Two concentric spheres lit by a single white light, used as a fixture
+/// by the world and camera tests.
+pub fn default_world() -> World {
+    let mut world = World::new();
+    world.add_light(PointLight::new(
+        Point::new(-10., 10., -10.),
+        Color::new(1., 1., 1.),
+    ));
+    world.add_object(
+        Sphere::unit_sphere(),
+        Material::new(Color::new(0.8, 1.0, 0.6), 0.1, 0.7, 0.2, 200.),
+    );
+    world.add_object(
+        Matrix4::scaling(0.5, 0.5, 0.5) * Sphere::unit_sphere(),
+        Material::default(),
+    );
+    world
+}
+
 #[cfg(test)]
 mod tests {
     use crate::geo::objects::geometry::Geometry;
@@ -45,11 +79,11 @@ mod tests {
         let mut canvas = Canvas::new(300, 300);
 
         // Create a sphere at the center of the image
-        let mut sphere = Sphere::new(Point::new(150., 150., 50.0), 50.0);
+        let sphere = Sphere::new(Point::new(150., 150., 50.0), 50.0);
 
         let light_position = Point::new(200.0, 225.0, -200.0);
 
-        let background_color = Color::new(50.0, 50.0, 50.0); // Dark gray
+        let background_color = Color::new(50. / 255., 50. / 255., 50. / 255.); // Dark gray
         let shadow_color = Color::new(0.0, 0.0, 0.0); // Black
 
         // Draw the sphere shadow
@@ -68,18 +102,19 @@ mod tests {
                 // A valid intersection occurs when we have intersection points
                 // and at least one of them is positive (in front of the ray origin)
                 let has_valid_intersection =
-                    !intersections.is_empty() && intersections.iter().any(|&t| t > 0.0);
+                    !intersections.is_empty() && intersections.iter().any(|i| i.t > 0.0);
 
                 let color = if has_valid_intersection {
-                    shadow_color.clone()
+                    shadow_color
                 } else {
-                    background_color.clone()
+                    background_color
                 };
                 canvas.set_pixel(j, i, color);
             }
         }
 
         // Save the result
-        canvas.save_ppm("src/test/output/sphere_shadow.ppm");
+        let path = std::env::temp_dir().join("stingray-sphere-shadow.ppm");
+        canvas.save_ppm(&path.to_string_lossy()).unwrap();
     }
 }
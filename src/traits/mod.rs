@@ -1,4 +1,4 @@
-use crate::geo::vector::Vector;
+use crate::{color::Color, error::StingrayError, geo::vector::Vector};
 
 pub trait ApproxEq {
     fn approx_cmp(&self, other: &Self) -> bool;
@@ -17,6 +17,20 @@ impl ApproxEq for Vector {
     }
 }
 
+impl ApproxEq for Color {
+    fn approx_cmp(&self, other: &Self) -> bool {
+        self.r.approx_cmp(&other.r) && self.g.approx_cmp(&other.g) && self.b.approx_cmp(&other.b)
+    }
+}
+
+/// Multiplication that reports operands of mismatched sizes instead of
+/// panicking like `*` does.
+pub trait TryMul<Rhs> {
+    type Output;
+
+    fn try_mul(self, rhs: Rhs) -> Result<Self::Output, StingrayError>;
+}
+
 pub trait SpecificRound {
     fn specific_round(&self, places: u32) -> Self;
 }
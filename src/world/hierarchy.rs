@@ -0,0 +1,80 @@
+use crate::geo::{
+    objects::bounds::{split_at_median, BoundingBox},
+    ray::Ray,
+};
+
+use super::Object;
+
+/// A bounding volume hierarchy over the world's own objects. Unlike a
+/// `Group` it refers to objects by index, so each keeps its material.
+pub(crate) struct Hierarchy {
+    root: Node,
+}
+
+struct Node {
+    bounds: BoundingBox,
+    /// Objects tested whenever the node is, such as unbounded planes.
+    objects: Vec<usize>,
+    children: Vec<Node>,
+}
+
+impl Hierarchy {
+    /// Splits the objects until no node holds more than `threshold`.
+    pub(crate) fn new(objects: &[Object], threshold: usize) -> Hierarchy {
+        let bounds: Vec<BoundingBox> = objects
+            .iter()
+            .map(|object| object.geometry.parent_space_bounds())
+            .collect();
+        Hierarchy {
+            root: Node::new((0..objects.len()).collect(), &bounds, threshold),
+        }
+    }
+
+    /// The indices of the objects whose bounds `ray` may pass through.
+    pub(crate) fn candidates(&self, ray: &Ray) -> Vec<usize> {
+        let mut candidates = vec![];
+        self.root.collect(ray, &mut candidates);
+        candidates
+    }
+}
+
+impl Node {
+    /// Splits the finite objects in two with `split_at_median`, as
+    /// `Group::divide` does, leaving unbounded ones at this node.
+    fn new(indices: Vec<usize>, bounds: &[BoundingBox], threshold: usize) -> Node {
+        let mut node = Node {
+            bounds: BoundingBox::empty(),
+            objects: vec![],
+            children: vec![],
+        };
+        for &index in indices.iter() {
+            node.bounds.merge(&bounds[index]);
+        }
+
+        let (finite, unbounded): (Vec<_>, Vec<_>) = indices
+            .into_iter()
+            .partition(|&index| bounds[index].is_finite());
+        node.objects = unbounded;
+        if finite.len() + node.objects.len() <= threshold || finite.len() < 2 {
+            node.objects.extend(finite);
+            return node;
+        }
+
+        let (left, right) = split_at_median(finite, |&index| bounds[index]);
+        node.children = vec![
+            Node::new(left, bounds, threshold),
+            Node::new(right, bounds, threshold),
+        ];
+        node
+    }
+
+    fn collect(&self, ray: &Ray, candidates: &mut Vec<usize>) {
+        if !self.bounds.intersects(ray) {
+            return;
+        }
+        candidates.extend(self.objects.iter().copied());
+        for child in self.children.iter() {
+            child.collect(ray, candidates);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+mod hierarchy;
+
+use hierarchy::Hierarchy;
+
+use crate::{
+    canvas::scene_object::SceneObject,
+    color::Color,
+    error::StingrayError,
+    geo::{
+        objects::{
+            bounds::BoundingBox,
+            geometry::Geometry,
+            hit::Hit,
+            intersection::{Computations, Intersections},
+        },
+        point::Point,
+        ray::Ray,
+    },
+    lighting::{lighting, Material, PointLight},
+};
+
+pub type Object = SceneObject<Box<dyn Geometry>, Material>;
+
+#[derive(Default)]
+pub struct World {
+    objects: Vec<Object>,
+    pub lights: Vec<PointLight>,
+    hierarchy: Option<Hierarchy>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        World::default()
+    }
+
+    pub fn add_object<G: Geometry + 'static>(&mut self, geometry: G, material: Material) {
+        self.push_object(SceneObject::new(Box::new(geometry), material));
+    }
+
+    pub fn push_object(&mut self, object: Object) {
+        self.hierarchy = None;
+        self.objects.push(object);
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// The object at `index`, for editing. Its bounds may change, so the
+    /// top level hierarchy is dropped until the next `divide`.
+    pub fn object_mut(&mut self, index: usize) -> Option<&mut Object> {
+        self.hierarchy = None;
+        self.objects.get_mut(index)
+    }
+
+    pub fn remove_object(&mut self, index: usize) -> Object {
+        self.hierarchy = None;
+        self.objects.remove(index)
+    }
+
+    pub fn add_light(&mut self, light: PointLight) {
+        self.lights.push(light);
+    }
+
+    /// Builds a bounding volume hierarchy inside every composite object,
+    /// with at most `threshold` children per group, and over the world's own
+    /// objects when there are more than `threshold` of them. Adding,
+    /// removing or editing objects drops the top level hierarchy until it
+    /// is called again.
+    pub fn divide(&mut self, threshold: usize) {
+        for object in self.objects.iter_mut() {
+            object.geometry.divide(threshold);
+        }
+        self.hierarchy =
+            (self.objects.len() > threshold).then(|| Hierarchy::new(&self.objects, threshold));
+    }
+
+    /// The box enclosing every object, in world space.
+    pub fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for object in self.objects.iter() {
+            bounds.merge(&object.geometry.parent_space_bounds());
+        }
+        bounds
+    }
+
+    /// How many triangles the objects in the world are made of.
+    pub fn triangle_count(&self) -> usize {
+        self.objects
+            .iter()
+            .map(|object| object.geometry.triangle_count())
+            .sum()
+    }
+
+    /// Every intersection of `ray` with the objects in the world, sorted by `t`.
+    pub fn intersect_world(&self, ray: &Ray) -> Result<Intersections<'_>, StingrayError> {
+        let mut intersections = vec![];
+        let objects: Box<dyn Iterator<Item = &Object>> = match &self.hierarchy {
+            Some(hierarchy) => Box::new(
+                hierarchy
+                    .candidates(ray)
+                    .into_iter()
+                    .map(|index| &self.objects[index]),
+            ),
+            None => Box::new(self.objects.iter()),
+        };
+        for object in objects {
+            let xs = object.geometry.intersect(ray)?;
+            intersections.extend(xs.into_iter().map(|i| i.or_material(&object.material)));
+        }
+        Ok(Intersections::new(intersections))
+    }
+
+    /// Shades an intersection with every light in the world, leaving out the
+    /// lights that other objects block. The hit must come from
+    /// `intersect_world`, which tags it with its object's material.
+    pub fn shade_hit(&self, comps: &Computations) -> Result<Color, StingrayError> {
+        let material = comps.material.ok_or(StingrayError::InvalidArgument(
+            "cannot shade a hit without a material".to_string(),
+        ))?;
+        let mut color = Color::black();
+        for light in self.lights.iter() {
+            let in_shadow = self.is_shadowed(comps.over_point, light)?;
+            color = color
+                + lighting(
+                    material,
+                    light,
+                    comps.over_point,
+                    comps.eye,
+                    comps.normal,
+                    in_shadow,
+                );
+        }
+        Ok(color)
+    }
+
+    /// Whether any object lies between `point` and `light`.
+    pub fn is_shadowed(&self, point: Point, light: &PointLight) -> Result<bool, StingrayError> {
+        let to_light = light.position - point;
+        let distance = to_light.mag();
+        let ray = Ray::new(point, to_light.norm());
+
+        let intersections = self.intersect_world(&ray)?;
+        Ok(intersections.hit().is_some_and(|hit| hit.t < distance))
+    }
+
+    pub fn color_at(&self, ray: &Ray) -> Result<Color, StingrayError> {
+        let intersections = self.intersect_world(ray)?;
+        let Some(hit) = intersections.hit() else {
+            return Ok(Color::black());
+        };
+        let comps = hit.prepare_computations(ray)?;
+        self.shade_hit(&comps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geo::{
+            matrix4::Matrix4,
+            objects::{
+                group::Group, intersection::Intersection, plane::Plane, sphere::Sphere,
+                triangle::Triangle,
+            },
+            vector::Vector,
+        },
+        test::utils::{default_world, ts},
+        traits::SpecificRound,
+    };
+
+    #[test]
+    fn test_intersect_world() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let intersections = world.intersect_world(&ray).unwrap();
+
+        assert_eq!(ts(&intersections), vec![4., 4.5, 5.5, 6.]);
+    }
+
+    #[test]
+    fn test_color_at_miss() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 1., 0.));
+        let color = world.color_at(&ray).unwrap();
+
+        assert_eq!((color.r, color.g, color.b), (0., 0., 0.));
+    }
+
+    #[test]
+    fn test_color_at_hit() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let color = world.color_at(&ray).unwrap();
+
+        assert_eq!(color.r.specific_round(5), 0.38066);
+        assert_eq!(color.g.specific_round(5), 0.47583);
+        assert_eq!(color.b.specific_round(5), 0.2855);
+    }
+
+    #[test]
+    fn test_color_at_from_inside() {
+        let mut world = default_world();
+        world.lights = vec![PointLight::new(Point::new(0., 0.25, 0.), Color::white())];
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let color = world.color_at(&ray).unwrap();
+
+        assert_eq!(color.r.specific_round(5), 0.90498);
+        assert_eq!(color.g.specific_round(5), 0.90498);
+        assert_eq!(color.b.specific_round(5), 0.90498);
+    }
+
+    #[test]
+    fn test_is_shadowed() {
+        let world = default_world();
+        let light = &world.lights[0];
+
+        // Nothing is collinear with the point and the light
+        assert!(!world.is_shadowed(Point::new(0., 10., 0.), light).unwrap());
+        // An object sits between the point and the light
+        assert!(world
+            .is_shadowed(Point::new(10., -10., 10.), light)
+            .unwrap());
+        // The object is behind the light
+        assert!(!world
+            .is_shadowed(Point::new(-20., 20., -20.), light)
+            .unwrap());
+        // The object is behind the point
+        assert!(!world.is_shadowed(Point::new(-2., 2., -2.), light).unwrap());
+    }
+
+    #[test]
+    fn test_shade_hit_in_shadow() {
+        let mut world = World::new();
+        world.add_light(PointLight::new(Point::new(0., 0., -10.), Color::white()));
+        world.add_object(Sphere::unit_sphere(), Material::default());
+        world.add_object(
+            Matrix4::translation(0., 0., 10.) * Sphere::unit_sphere(),
+            Material::default(),
+        );
+        let ray = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
+        let color = world.color_at(&ray).unwrap();
+
+        assert_eq!(color.r.specific_round(5), 0.1);
+        assert_eq!(color.g.specific_round(5), 0.1);
+        assert_eq!(color.b.specific_round(5), 0.1);
+    }
+
+    #[test]
+    fn test_shade_hit_without_material() {
+        let world = default_world();
+        let sphere = Sphere::unit_sphere();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let hit = Intersection::new(4., &sphere);
+        let comps = hit.prepare_computations(&ray).unwrap();
+
+        assert!(matches!(
+            world.shade_hit(&comps),
+            Err(StingrayError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_shade_hit_one_of_two_lights_blocked() {
+        let mut world = World::new();
+        world.add_light(PointLight::new(Point::new(0., 0., -10.), Color::white()));
+        world.add_light(PointLight::new(Point::new(0., 0., 2.), Color::white()));
+        world.add_object(Sphere::unit_sphere(), Material::default());
+        world.add_object(
+            Matrix4::translation(0., 0., 10.) * Sphere::unit_sphere(),
+            Material::default(),
+        );
+        let ray = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
+        let color = world.color_at(&ray).unwrap();
+
+        // Ambient from the blocked light plus full diffuse and specular from the other
+        assert_eq!(color.r.specific_round(5), 2.);
+    }
+
+    #[test]
+    fn test_color_at_multiple_lights() {
+        let mut world = default_world();
+        let light = world.lights[0];
+        world.add_light(light);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let color = world.color_at(&ray).unwrap();
+
+        assert_eq!(color.r.specific_round(5), 0.76132);
+    }
+
+    #[test]
+    fn test_divide_top_level_objects() {
+        let build = || {
+            let mut world = World::new();
+            for i in 0..20 {
+                let x = i as f64 * 3.;
+                world.add_object(
+                    Matrix4::translation(x, 0., 0.) * Sphere::unit_sphere(),
+                    Material::default(),
+                );
+            }
+            world.add_object(
+                Matrix4::translation(0., -1., 0.) * Plane::new(),
+                Material::default(),
+            );
+            world
+        };
+        let flat = build();
+        let mut divided = build();
+        divided.divide(4);
+
+        let ray = Ray::new(Point::new(30., 5., 0.), Vector::new(0., -1., 0.));
+        let hierarchy = divided.hierarchy.as_ref().unwrap();
+        // The sphere under the ray and the plane, not all twenty one objects
+        assert!(hierarchy.candidates(&ray).len() <= 5);
+        assert_eq!(
+            ts(&divided.intersect_world(&ray).unwrap()),
+            ts(&flat.intersect_world(&ray).unwrap())
+        );
+        for x in [-2., 0.5, 29.5, 57.] {
+            let ray = Ray::new(Point::new(x, 0.3, -5.), Vector::new(0.1, -0.2, 1.).norm());
+            assert_eq!(
+                ts(&divided.intersect_world(&ray).unwrap()),
+                ts(&flat.intersect_world(&ray).unwrap())
+            );
+        }
+
+        // A new object is still found before the hierarchy is rebuilt
+        divided.add_object(
+            Matrix4::translation(30., 3., 0.) * Sphere::unit_sphere(),
+            Material::default(),
+        );
+        assert_eq!(divided.intersect_world(&ray).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_moved_object_is_hit_after_divide() {
+        let mut world = World::new();
+        for i in 0..10 {
+            world.add_object(
+                Matrix4::translation(i as f64 * 3., 0., 0.) * Sphere::unit_sphere(),
+                Material::default(),
+            );
+        }
+        world.divide(2);
+        let ray = Ray::new(Point::new(0., 50., -5.), Vector::new(0., 0., 1.));
+        assert!(world.intersect_world(&ray).unwrap().is_empty());
+
+        // Lift the first sphere into the ray's path
+        world
+            .object_mut(0)
+            .unwrap()
+            .geometry
+            .set_transform(Matrix4::translation(0., 50., 0.));
+        assert_eq!(ts(&world.intersect_world(&ray).unwrap()), vec![4., 6.]);
+
+        world.divide(2);
+        assert_eq!(ts(&world.intersect_world(&ray).unwrap()), vec![4., 6.]);
+        world.remove_object(0);
+        assert!(world.intersect_world(&ray).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_world_bounds_and_triangle_count() {
+        let mut world = default_world();
+        assert_eq!(world.triangle_count(), 0);
+        let bounds = world.bounds();
+        assert_eq!(bounds.min, Point::new(-1., -1., -1.));
+        assert_eq!(bounds.max, Point::new(1., 1., 1.));
+
+        let mut group = Group::new();
+        group.add_child(Triangle::new(
+            Point::new(0., 1., 0.),
+            Point::new(-1., 0., 0.),
+            Point::new(1., 0., 0.),
+        ));
+        group.add_child(Triangle::new(
+            Point::new(0., 1., 0.),
+            Point::new(1., 0., 0.),
+            Point::new(0., 0., 4.),
+        ));
+        world.add_object(group, Material::default());
+
+        assert_eq!(world.triangle_count(), 2);
+        assert_eq!(world.bounds().max, Point::new(1., 1., 4.));
+    }
+}